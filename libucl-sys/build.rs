@@ -1,48 +1,238 @@
-use std::process::Command;
+extern crate bindgen;
+extern crate cc;
+extern crate pkg_config;
+
 use std::env;
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{
+    Path,
+    PathBuf
+};
+
+/// Oldest libucl release providing all functions declared in bindings.
+const MIN_VERSION: &str = "0.8.2";
+
+/// Headers available on every supported Unix target. libucl checks them with `HAVE_*` macros
+/// normally defined by `configure`.
+const UNIX_HEADERS: &[&str] = &[
+    "HAVE_CTYPE_H",
+    "HAVE_ERRNO_H",
+    "HAVE_FCNTL_H",
+    "HAVE_FLOAT_H",
+    "HAVE_LIBGEN_H",
+    "HAVE_LIMITS_H",
+    "HAVE_LOCALE_H",
+    "HAVE_MATH_H",
+    "HAVE_STDARG_H",
+    "HAVE_STDBOOL_H",
+    "HAVE_STDDEF_H",
+    "HAVE_STDINT_H",
+    "HAVE_STDIO_H",
+    "HAVE_STDLIB_H",
+    "HAVE_STRING_H",
+    "HAVE_STRINGS_H",
+    "HAVE_SYS_MMAN_H",
+    "HAVE_SYS_PARAM_H",
+    "HAVE_SYS_STAT_H",
+    "HAVE_SYS_TYPES_H",
+    "HAVE_TIME_H",
+    "HAVE_UNISTD_H"
+];
+
+/// Headers available on Windows.
+const WINDOWS_HEADERS: &[&str] = &[
+    "HAVE_CTYPE_H",
+    "HAVE_ERRNO_H",
+    "HAVE_FCNTL_H",
+    "HAVE_FLOAT_H",
+    "HAVE_LIMITS_H",
+    "HAVE_LOCALE_H",
+    "HAVE_MATH_H",
+    "HAVE_STDARG_H",
+    "HAVE_STDBOOL_H",
+    "HAVE_STDDEF_H",
+    "HAVE_STDINT_H",
+    "HAVE_STDIO_H",
+    "HAVE_STDLIB_H",
+    "HAVE_STRING_H",
+    "HAVE_SYS_STAT_H",
+    "HAVE_SYS_TYPES_H",
+    "HAVE_TIME_H"
+];
 
 fn main() {
-    let src = PathBuf::from(&env::var("CARGO_MANIFEST_DIR").unwrap());
-    let dst = PathBuf::from(&env::var("OUT_DIR").unwrap());
-
-    let mut cmd = Command::new("./autogen.sh");
-    cmd.current_dir(&src.join("libucl"));
-
-    run(&mut cmd, "autogen.sh");
-
-    let mut cmd = Command::new("./configure");
-    cmd
-        .current_dir(&src.join("libucl"))
-        .arg(&format!("--prefix={}", dst.display()));
-    run(cmd.arg("--enable-urls")
-           .arg("--enable-regex")
-           .arg("--disable-shared")
-           .arg("--disable-dependency-tracking")
-           .arg("--with-pic"), "configure");
-
-    let mut cmd = Command::new("make");
-    cmd
-        .current_dir(&src.join("libucl"));
-    run(cmd.arg("install"), "make");
-
-    println!("cargo:rustc-link-lib=static=ucl");
-    println!("cargo:rustc-link-search=native={}", dst.join("lib").display());
-}
-
-fn run(cmd: &mut Command, program: &str) {
-    println!("running: {:?}", cmd);
-    let status = match cmd.status() {
-        Ok(status) => status,
-        Err(ref e) if e.kind() == ErrorKind::NotFound => {
-            fail(&format!("failed to execute command: {}\nis `{}` not installed?",
-                          e, program));
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=shim.c");
+    println!("cargo:rerun-if-env-changed=UCL_SYS_USE_PKG_CONFIG");
+
+    let src = PathBuf::from(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("libucl");
+    let vendored = src.join("src").is_dir();
+
+    // without submodule checked out system library is the only option
+    if !vendored {
+        println!("cargo:rerun-if-changed={}", src.display());
+    }
+
+    if use_system() || !vendored {
+        match probe_system() {
+            Ok(()) => return,
+            Err(e) if vendored => {
+                println!("cargo:warning=system libucl >= {} not found, using vendored copy: {}",
+                         MIN_VERSION,
+                         e.to_string().lines().next().unwrap_or_default());
+            },
+            Err(e) => {
+                fail(&format!("libucl sources not found in {}\n\
+                               run `git submodule update --init` first or install libucl >= {}\n\n\
+                               system library not found: {}", src.display(), MIN_VERSION, e));
+            }
+        }
+    }
+
+    build_vendored(&src);
+}
+
+fn shim() -> PathBuf {
+    PathBuf::from(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("shim.c")
+}
+
+/// Check if system library was requested with `system` feature or `UCL_SYS_USE_PKG_CONFIG`.
+fn use_system() -> bool {
+    let var = env::var("UCL_SYS_USE_PKG_CONFIG").map(|val| val != "0").unwrap_or(false);
+
+    var || env::var("CARGO_FEATURE_SYSTEM").is_ok()
+}
+
+fn probe_system() -> Result<(), pkg_config::Error> {
+    let lib = pkg_config::Config::new()
+        .atleast_version(MIN_VERSION)
+        .cargo_metadata(false)
+        .probe("libucl")?;
+
+    // shim must precede the library on linker command line
+    let mut build = cc::Build::new();
+    build.file(shim()).includes(&lib.include_paths).warnings(false);
+    build.compile("ucl_sys_shim");
+
+    for path in &lib.link_paths {
+        println!("cargo:rustc-link-search=native={}", path.display());
+    }
+    for name in &lib.libs {
+        println!("cargo:rustc-link-lib={}", name);
+    }
+    for path in &lib.include_paths {
+        println!("cargo:include={}", path.display());
+    }
+
+    generate_bindings(&lib.include_paths);
+
+    Ok(())
+}
+
+fn build_vendored(src: &Path) {
+    // target configuration, not the host one, so cross-compilation works
+    let unix = env::var("CARGO_CFG_TARGET_FAMILY").map(|family| family == "unix").unwrap_or(false);
+
+    let mut build = cc::Build::new();
+    build
+        .include(src.join("include"))
+        .include(src.join("src"))
+        .include(src.join("uthash"))
+        .include(src.join("klib"))
+        .warnings(false)
+        .pic(true);
+
+    for define in if unix { UNIX_HEADERS } else { WINDOWS_HEADERS } {
+        build.define(define, "1");
+    }
+
+    if env::var("CARGO_FEATURE_REGEX").is_ok() {
+        if !unix { fail("`regex` feature requires POSIX regex.h") }
+        build.define("HAVE_REGEX_H", "1");
+    }
+
+    if env::var("CARGO_FEATURE_URLS").is_ok() {
+        build.define("CURL_FOUND", "1");
+        // set by curl-sys when it builds bundled libcurl
+        if let Ok(include) = env::var("DEP_CURL_INCLUDE") {
+            build.include(include);
+        }
+    }
+
+    if env::var("CARGO_FEATURE_SIGNATURES").is_ok() {
+        build.define("HAVE_OPENSSL", "1");
+        // set by openssl-sys
+        if let Ok(include) = env::var("DEP_OPENSSL_INCLUDE") {
+            build.include(include);
         }
-        Err(e) => fail(&format!("failed to execute command: {}", e)),
-    };
-    if !status.success() {
-        fail(&format!("command did not execute successfully, got: {}", status));
+    }
+
+    for file in sources(&src.join("src")) {
+        build.file(file);
+    }
+    build.file(shim());
+
+    build.compile("ucl");
+
+    for dir in &["include", "src", "uthash", "klib"] {
+        rerun_if_changed(&src.join(dir));
+    }
+    println!("cargo:include={}", src.join("include").display());
+
+    generate_bindings(&[src.join("include")]);
+}
+
+/// Generate bindings to everything declared in `ucl.h` found in `include_paths`.
+///
+/// Bindings are generated on every build instead of being checked in, as they must match the
+/// header of the library actually linked: the vendored copy or any system release since
+/// `MIN_VERSION`, whose declarations and struct layouts differ between releases and targets.
+fn generate_bindings(include_paths: &[PathBuf]) {
+    let out = PathBuf::from(&env::var("OUT_DIR").unwrap()).join("bindings.rs");
+
+    let bindings = bindgen::Builder::default()
+        .header_contents("wrapper.h", "#include <ucl.h>")
+        .clang_args(include_paths.iter().map(|path| format!("-I{}", path.display())))
+        .allowlist_function("ucl_.*")
+        .allowlist_type("ucl_.*")
+        // macros in current releases, declared in `lib.rs` and linked to wrappers from `shim.c`
+        .blocklist_function("ucl_(iterate_object|object_iterate_safe|object_find_keyl?|lookup_path(_char)?)")
+        // sets of flags are defined with `bitflags!` in `lib.rs`
+        .blocklist_type("ucl_(parser|string|object)_flags(_t)?")
+        // taken from `libc`
+        .blocklist_type("FILE")
+        .rustified_enum("ucl_(error|type|emitter|duplicate_strategy|parse_type|schema_error_code)")
+        .ctypes_prefix("libc")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+        .generate()
+        .unwrap_or_else(|e| fail(&format!("failed to generate bindings from ucl.h: {}", e)));
+
+    if let Err(e) = bindings.write_to_file(&out) {
+        fail(&format!("failed to write {}: {}", out.display(), e));
+    }
+}
+
+fn sources(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = read_dir(dir).into_iter()
+        .filter(|path| path.extension().map_or(false, |ext| ext == "c"))
+        .collect();
+    files.sort();
+
+    files
+}
+
+fn rerun_if_changed(dir: &Path) {
+    for path in read_dir(dir) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
+
+fn read_dir(dir: &Path) -> Vec<PathBuf> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+        // optional in older libucl releases
+        Err(_) if dir.ends_with("klib") || dir.ends_with("uthash") => Vec::new(),
+        Err(e) => fail(&format!("failed to read {}: {}", dir.display(), e))
     }
 }
 
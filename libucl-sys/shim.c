@@ -0,0 +1,79 @@
+/*
+ * Glue compiled against the same `ucl.h` as the library.
+ *
+ * Functions below are macros or were renamed between libucl releases, so bindings link to these
+ * wrappers instead. Constants describe layout of public structures and values of enums, which
+ * are checked by tests in `lib.rs`.
+ */
+
+#include <stddef.h>
+#include <ucl.h>
+
+const ucl_object_t *
+ucl_sys_iterate_object (const ucl_object_t *obj, ucl_object_iter_t *iter, bool expand_values)
+{
+	return ucl_iterate_object (obj, iter, expand_values);
+}
+
+const ucl_object_t *
+ucl_sys_object_iterate_safe (ucl_object_iter_t iter, bool expand_values)
+{
+	return ucl_object_iterate_safe (iter, expand_values);
+}
+
+const ucl_object_t *
+ucl_sys_object_find_key (const ucl_object_t *obj, const char *key)
+{
+	return ucl_object_find_key (obj, key);
+}
+
+const ucl_object_t *
+ucl_sys_object_find_keyl (const ucl_object_t *obj, const char *key, size_t klen)
+{
+	return ucl_object_find_keyl (obj, key, klen);
+}
+
+const ucl_object_t *
+ucl_sys_lookup_path (const ucl_object_t *obj, const char *path)
+{
+	return ucl_lookup_path (obj, path);
+}
+
+const ucl_object_t *
+ucl_sys_lookup_path_char (const ucl_object_t *obj, const char *path, char sep)
+{
+	return ucl_lookup_path_char (obj, path, sep);
+}
+
+const size_t ucl_sys_sizeof_object = sizeof (ucl_object_t);
+const size_t ucl_sys_offsetof_object_key = offsetof (ucl_object_t, key);
+const size_t ucl_sys_offsetof_object_next = offsetof (ucl_object_t, next);
+const size_t ucl_sys_offsetof_object_keylen = offsetof (ucl_object_t, keylen);
+const size_t ucl_sys_offsetof_object_flags = offsetof (ucl_object_t, flags);
+const size_t ucl_sys_offsetof_object_trash_stack = offsetof (ucl_object_t, trash_stack);
+const size_t ucl_sys_sizeof_userdata = sizeof (struct ucl_object_userdata);
+const size_t ucl_sys_sizeof_emitter_functions = sizeof (struct ucl_emitter_functions);
+const size_t ucl_sys_sizeof_emitter_operations = sizeof (struct ucl_emitter_operations);
+const size_t ucl_sys_sizeof_emitter_context = sizeof (struct ucl_emitter_context);
+const size_t ucl_sys_sizeof_schema_error = sizeof (struct ucl_schema_error);
+
+const int ucl_sys_error_values[] = {
+	UCL_EOK, UCL_ESYNTAX, UCL_EIO, UCL_ESTATE, UCL_ENESTED, UCL_EUNPAIRED, UCL_EMACRO,
+	UCL_EINTERNAL, UCL_ESSL
+};
+
+const int ucl_sys_type_values[] = {
+	UCL_OBJECT, UCL_ARRAY, UCL_INT, UCL_FLOAT, UCL_STRING, UCL_BOOLEAN, UCL_TIME,
+	UCL_USERDATA, UCL_NULL
+};
+
+const int ucl_sys_emitter_values[] = {
+	UCL_EMIT_JSON, UCL_EMIT_JSON_COMPACT, UCL_EMIT_CONFIG, UCL_EMIT_YAML
+};
+
+const int ucl_sys_schema_error_values[] = {
+	UCL_SCHEMA_OK, UCL_SCHEMA_TYPE_MISMATCH, UCL_SCHEMA_INVALID_SCHEMA,
+	UCL_SCHEMA_MISSING_PROPERTY, UCL_SCHEMA_CONSTRAINT, UCL_SCHEMA_MISSING_DEPENDENCY,
+	UCL_SCHEMA_EXTERNAL_REF_MISSING, UCL_SCHEMA_EXTERNAL_REF_INVALID,
+	UCL_SCHEMA_INTERNAL_ERROR, UCL_SCHEMA_UNKNOWN
+};
@@ -0,0 +1,470 @@
+//! Call every binding once, so signatures that do not match `ucl.h` fail loudly.
+
+extern crate libc;
+extern crate libucl_sys;
+
+use libc::{
+    c_char,
+    c_double,
+    c_int,
+    c_uchar,
+    c_void,
+    size_t
+};
+use libucl_sys::*;
+
+use std::ffi::{
+    CStr,
+    CString
+};
+use std::mem;
+use std::ptr;
+
+fn c(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+unsafe fn parse(doc: &str) -> *mut ucl_object_t {
+    let parser = ucl_parser_new(0);
+    assert!(ucl_parser_add_chunk(parser, doc.as_ptr(), doc.len()));
+    let obj = ucl_parser_get_object(parser);
+    ucl_parser_free(parser);
+
+    assert!(!obj.is_null());
+    obj
+}
+
+unsafe fn emit(obj: *const ucl_object_t) -> String {
+    let out = ucl_object_emit(obj, ucl_emitter::UCL_EMIT_JSON_COMPACT);
+    let res = CStr::from_ptr(out as *const c_char).to_string_lossy().into_owned();
+    libc::free(out as *mut c_void);
+
+    res
+}
+
+unsafe extern "C" fn macro_handler(_: *const c_uchar, _: size_t, _: *const ucl_object_t, ud: *mut c_void) -> bool {
+    *(ud as *mut bool) = true;
+    true
+}
+
+unsafe extern "C" fn context_handler(_: *const c_uchar, _: size_t, _: *const ucl_object_t, _: *const ucl_object_t, ud: *mut c_void) -> bool {
+    *(ud as *mut bool) = true;
+    true
+}
+
+unsafe extern "C" fn variable_handler(_: *const c_uchar, _: size_t, replace: *mut *mut c_uchar, len: *mut size_t, need_free: *mut bool, _: *mut c_void) -> bool {
+    *replace = b"unknown\0".as_ptr() as *mut c_uchar;
+    *len = 7;
+    *need_free = false;
+    true
+}
+
+unsafe extern "C" fn userdata_dtor(ptr: *mut c_void) {
+    *(ptr as *mut bool) = true;
+}
+
+unsafe extern "C" fn userdata_emitter(_: *mut c_void) -> *const c_char {
+    b"userdata\0".as_ptr() as *const c_char
+}
+
+unsafe extern "C" fn reverse(o1: *mut *const ucl_object_t, o2: *mut *const ucl_object_t) -> c_int {
+    -ucl_object_compare_qsort(o1, o2)
+}
+
+#[test]
+fn parser() {
+    unsafe {
+        let parser = ucl_parser_new(UCL_PARSER_SAVE_COMMENTS.bits());
+        let mut called = false;
+        let mut context_called = false;
+        let name = c("test");
+        let context_name = c("ctx");
+        let var = c("VAR");
+        let value = c("value");
+
+        ucl_parser_register_macro(parser, name.as_ptr(), Some(macro_handler), &mut called as *mut bool as *mut c_void);
+        ucl_parser_register_context_macro(parser, context_name.as_ptr(), Some(context_handler), &mut context_called as *mut bool as *mut c_void);
+        ucl_parser_register_variable(parser, var.as_ptr(), value.as_ptr());
+        ucl_parser_set_variables_handler(parser, Some(variable_handler), ptr::null_mut());
+        assert!(ucl_parser_set_default_priority(parser, 1));
+        assert!(ucl_parser_set_filevars(parser, ptr::null(), false));
+
+        let chunk = b"# comment\na = $VAR; b = $OTHER; .test \"x\"\nc { .ctx \"y\" }";
+        assert!(ucl_parser_add_chunk(parser, chunk.as_ptr(), chunk.len()));
+        assert!(ucl_parser_add_chunk_priority(parser, b"d = 1".as_ptr(), 5, 2));
+        assert!(ucl_parser_add_chunk_full(parser, b"e = 2".as_ptr(), 5, 0,
+                                          ucl_duplicate_strategy::UCL_DUPLICATE_APPEND,
+                                          ucl_parse_type::UCL_PARSE_UCL));
+        assert!(ucl_parser_add_string(parser, b"f = 3".as_ptr() as *const c_char, 5));
+        assert!(ucl_parser_add_string_priority(parser, b"g = 4".as_ptr() as *const c_char, 5, 1));
+        assert!(called && context_called);
+
+        let obj = ucl_parser_get_object(parser);
+        assert_eq!(emit(obj), r#"{"a":"value","b":"unknown","c":{},"d":1,"e":2,"f":3,"g":4}"#);
+
+        let comments = ucl_parser_get_comments(parser);
+        assert!(!comments.is_null());
+        let a = ucl_object_find_key(obj, b"a\0".as_ptr() as *const c_char);
+        assert!(!ucl_comments_find(comments, a).is_null());
+
+        assert!(!ucl_parser_add_chunk(parser, b"{".as_ptr(), 1));
+        assert!(!ucl_parser_get_error(parser).is_null());
+        assert_ne!(ucl_parser_get_error_code(parser), ucl_error_t::UCL_EOK as c_int);
+        ucl_parser_get_error_column(parser);
+        ucl_parser_get_error_linenum(parser);
+        ucl_parser_clear_error(parser);
+        assert!(ucl_parser_get_error(parser).is_null());
+
+        ucl_object_unref(obj);
+        ucl_parser_free(parser);
+    }
+}
+
+#[test]
+fn parser_files() {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+
+    let path = std::env::temp_dir().join("libucl-sys-bindings.conf");
+    File::create(&path).unwrap().write_all(b"a = 1").unwrap();
+    let filename = c(path.to_str().unwrap());
+
+    unsafe {
+        let parser = ucl_parser_new(0);
+        assert!(ucl_parser_add_file(parser, filename.as_ptr()));
+        assert!(ucl_parser_add_file_priority(parser, filename.as_ptr(), 1));
+
+        let file = File::open(&path).unwrap();
+        assert!(ucl_parser_add_fd(parser, file.as_raw_fd()));
+        let file = File::open(&path).unwrap();
+        assert!(ucl_parser_add_fd_priority(parser, file.as_raw_fd(), 2));
+
+        let obj = ucl_parser_get_object(parser);
+        assert_eq!(ucl_object_get_priority(ucl_object_find_key(obj, b"a\0".as_ptr() as *const c_char)), 2);
+
+        ucl_object_unref(obj);
+        ucl_parser_free(parser);
+    }
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn comments() {
+    unsafe {
+        let comments = ucl_object_typed_new(ucl_type_t::UCL_OBJECT);
+        let a = ucl_object_fromint(1);
+        let b = ucl_object_fromint(2);
+        let text = c("# comment");
+
+        ucl_comments_add(comments, a, text.as_ptr());
+        assert!(!ucl_comments_find(comments, a).is_null());
+        assert!(ucl_comments_move(comments, a, b));
+        assert!(ucl_comments_find(comments, a).is_null());
+        assert!(!ucl_comments_find(comments, b).is_null());
+
+        ucl_object_unref(a);
+        ucl_object_unref(b);
+        ucl_object_unref(comments);
+    }
+}
+
+#[test]
+fn pubkey() {
+    unsafe {
+        let parser = ucl_parser_new(0);
+        // fails without OpenSSL support or with invalid key, only signature is checked here
+        ucl_pubkey_add(parser, b"invalid".as_ptr(), 7);
+        ucl_parser_free(parser);
+    }
+}
+
+#[test]
+fn emitters() {
+    unsafe {
+        let obj = parse("a = [1, 2]");
+
+        let mut len = 0;
+        let out = ucl_object_emit_len(obj, ucl_emitter::UCL_EMIT_JSON_COMPACT, &mut len);
+        assert_eq!(len, 11);
+        libc::free(out as *mut c_void);
+
+        let mut mem: *mut c_void = ptr::null_mut();
+        let funcs = ucl_object_emit_memory_funcs(&mut mem);
+        assert!(ucl_object_emit_full(obj, ucl_emitter::UCL_EMIT_CONFIG, funcs, ptr::null()));
+        ucl_object_emit_funcs_free(funcs);
+        assert!(CStr::from_ptr(mem as *const c_char).to_bytes().starts_with(b"a"));
+        libc::free(mem);
+
+        let file = libc::tmpfile();
+        let funcs = ucl_object_emit_file_funcs(file);
+        assert!(ucl_object_emit_full(obj, ucl_emitter::UCL_EMIT_YAML, funcs, ptr::null()));
+        ucl_object_emit_funcs_free(funcs);
+        libc::fclose(file);
+
+        let file = libc::tmpfile();
+        let funcs = ucl_object_emit_fd_funcs(libc::fileno(file));
+        assert!(ucl_object_emit_full(obj, ucl_emitter::UCL_EMIT_JSON, funcs, ptr::null()));
+        ucl_object_emit_funcs_free(funcs);
+        libc::fclose(file);
+
+        let mut mem: *mut c_void = ptr::null_mut();
+        let funcs = ucl_object_emit_memory_funcs(&mut mem);
+        let ctx = ucl_object_emit_streamline_new(obj, ucl_emitter::UCL_EMIT_JSON_COMPACT, funcs);
+        let arr = ucl_object_typed_new(ucl_type_t::UCL_ARRAY);
+        let elt = ucl_object_fromint(3);
+        ucl_object_emit_streamline_start_container(ctx, arr);
+        ucl_object_emit_streamline_add_object(ctx, elt);
+        ucl_object_emit_streamline_end_container(ctx);
+        ucl_object_emit_streamline_finish(ctx);
+        ucl_object_emit_funcs_free(funcs);
+        libc::free(mem);
+
+        ucl_object_unref(elt);
+        ucl_object_unref(arr);
+        ucl_object_unref(obj);
+    }
+}
+
+#[test]
+fn conversions() {
+    unsafe {
+        let int = ucl_object_fromint(10);
+        let double = ucl_object_fromdouble(0.5);
+        let boolean = ucl_object_frombool(true);
+        let string = ucl_object_fromstring(b"lol\0".as_ptr() as *const c_char);
+
+        assert!(ucl_object_toboolean(boolean));
+        let mut b = false;
+        assert!(ucl_object_toboolean_safe(boolean, &mut b) && b);
+
+        assert_eq!(ucl_object_todouble(double), 0.5);
+        let mut d: c_double = 0.0;
+        assert!(ucl_object_todouble_safe(double, &mut d) && d == 0.5);
+
+        assert_eq!(ucl_object_toint(int), 10);
+        let mut i = 0;
+        assert!(ucl_object_toint_safe(int, &mut i) && i == 10);
+
+        let mut len = 0;
+        assert!(!ucl_object_tolstring(string, &mut len).is_null());
+        assert_eq!(len, 3);
+        assert_eq!(CStr::from_ptr(ucl_object_tostring(string)).to_bytes(), b"lol");
+        assert_eq!(CStr::from_ptr(ucl_object_tostring_forced(int)).to_bytes(), b"10");
+
+        let mut out = ptr::null();
+        assert!(ucl_object_tostring_safe(string, &mut out));
+        let mut len = 0;
+        assert!(ucl_object_tolstring_safe(string, &mut out, &mut len) && len == 3);
+        assert!(!ucl_object_tostring_safe(int, &mut out));
+
+        for obj in &[int, double, boolean, string] {
+            ucl_object_unref(*obj);
+        }
+    }
+}
+
+#[test]
+fn generation() {
+    unsafe {
+        let obj = ucl_object_new();
+        assert_eq!(ucl_object_type(obj) as c_int, ucl_type_t::UCL_NULL as c_int);
+        ucl_object_unref(obj);
+
+        let obj = ucl_object_new_full(ucl_type_t::UCL_ARRAY, 3);
+        assert_eq!(ucl_object_get_priority(obj), 3);
+        ucl_object_unref(obj);
+
+        let mut dropped = false;
+        let obj = ucl_object_new_userdata(Some(userdata_dtor), Some(userdata_emitter), &mut dropped as *mut bool as *mut c_void);
+        assert_eq!(emit(obj), "\"userdata\"");
+        ucl_object_unref(obj);
+        assert!(dropped);
+
+        let obj = ucl_object_fromlstring(b"lol".as_ptr() as *const c_char, 3);
+        assert_eq!(emit(obj), "\"lol\"");
+        ucl_object_unref(obj);
+
+        let obj = ucl_object_fromstring_common(b" 10k ".as_ptr() as *const c_char, 5, UCL_STRING_TRIM | UCL_STRING_PARSE);
+        assert_eq!(ucl_object_toint(obj), 10000);
+        ucl_object_unref(obj);
+    }
+}
+
+#[test]
+fn utilities() {
+    unsafe {
+        let obj = parse("a = 10");
+        let a = ucl_object_find_key(obj, b"a\0".as_ptr() as *const c_char);
+
+        assert_eq!(CStr::from_ptr(ucl_copy_key_trash(a)).to_bytes(), b"a");
+        assert_eq!(CStr::from_ptr(ucl_copy_value_trash(a)).to_bytes(), b"10");
+
+        let copy = ucl_object_copy(obj);
+        assert_eq!(ucl_object_compare(obj, copy), 0);
+        ucl_object_unref(copy);
+
+        assert_eq!(CStr::from_ptr(ucl_object_type_to_string(ucl_type_t::UCL_INT)).to_bytes(), b"integer");
+        let mut typ = ucl_type_t::UCL_NULL;
+        assert!(ucl_object_string_to_type(b"array\0".as_ptr() as *const c_char, &mut typ));
+        assert_eq!(typ as c_int, ucl_type_t::UCL_ARRAY as c_int);
+
+        ucl_object_unref(obj);
+    }
+}
+
+#[test]
+fn object_manipulation() {
+    unsafe {
+        let top = ucl_object_typed_new(ucl_type_t::UCL_OBJECT);
+        let key = |k: &str| (k.as_ptr() as *const c_char, k.len());
+
+        let (k, l) = key("a");
+        assert!(ucl_object_insert_key(top, ucl_object_fromint(1), k, l, true));
+        assert!(ucl_object_replace_key(top, ucl_object_fromint(2), k, l, true));
+        let (k, l) = key("b");
+        assert!(ucl_object_insert_key_merged(top, parse("c = 1"), k, l, true));
+        assert!(ucl_object_insert_key_merged(top, parse("d = 2"), k, l, true));
+        let other = parse("e = 3");
+        assert!(ucl_object_merge(top, other, true));
+        ucl_object_unref(other);
+        assert_eq!(emit(top), r#"{"a":2,"b":{"c":1,"d":2},"e":3}"#);
+
+        let (k, l) = key("e");
+        assert!(ucl_object_delete_keyl(top, k, l));
+        assert!(ucl_object_delete_key(top, b"b\0".as_ptr() as *const c_char));
+        let (k, l) = key("a");
+        let popped = ucl_object_pop_keyl(top, k, l);
+        assert_eq!(ucl_object_toint(popped), 2);
+        ucl_object_unref(popped);
+        assert!(ucl_object_pop_key(top, b"a\0".as_ptr() as *const c_char).is_null());
+
+        ucl_object_unref(top);
+    }
+}
+
+#[test]
+fn array_manipulation() {
+    unsafe {
+        let arr = ucl_object_typed_new(ucl_type_t::UCL_ARRAY);
+        let one = ucl_object_fromint(1);
+
+        assert!(ucl_array_append(arr, one));
+        assert!(ucl_array_prepend(arr, ucl_object_fromint(0)));
+        let more = parse("[2, 3]");
+        assert!(ucl_array_merge(arr, more, true));
+        ucl_object_unref(more);
+        assert_eq!(ucl_object_toint(ucl_array_head(arr)), 0);
+        assert_eq!(ucl_array_index_of(arr, one), 1);
+        assert_eq!(ucl_object_toint(ucl_array_find_index(arr, 1)), 1);
+
+        let old = ucl_array_replace_index(arr, ucl_object_fromint(10), 1);
+        assert_eq!(ucl_object_toint(old), 1);
+        ucl_object_unref(old);
+
+        let deleted = ucl_array_delete(arr, ucl_array_head(arr) as *mut ucl_object_t);
+        ucl_object_unref(deleted);
+
+        assert_eq!(ucl_object_toint(ucl_array_tail(arr)), 3);
+        let last = ucl_array_pop_last(arr);
+        assert_eq!(ucl_object_toint(last), 3);
+        ucl_object_unref(last);
+        let first = ucl_array_pop_first(arr);
+        assert_eq!(ucl_object_toint(first), 10);
+        ucl_object_unref(first);
+
+        let head = ucl_object_fromint(1);
+        let head = ucl_elt_append(head, ucl_object_fromint(2));
+        assert!(!(*head).next.is_null());
+        ucl_object_unref(head);
+
+        ucl_object_unref(arr);
+    }
+}
+
+#[test]
+fn iteration() {
+    unsafe {
+        let obj = parse("a = 1; b = 2; b = 3");
+
+        let mut it: ucl_object_iter_t = ptr::null_mut();
+        let mut count = 0;
+        while !ucl_iterate_object(obj, &mut it, true).is_null() { count += 1 }
+        assert_eq!(count, 2);
+
+        let it = ucl_object_iterate_new(obj);
+        let mut count = 0;
+        while !ucl_object_iterate_safe(it, false).is_null() { count += 1 }
+        assert_eq!(count, 2);
+
+        let it = ucl_object_iterate_reset(it, obj);
+        let mut count = 0;
+        while !ucl_object_iterate_safe(it, true).is_null() { count += 1 }
+        assert_eq!(count, 3);
+
+        let it = ucl_object_iterate_reset(it, obj);
+        assert!(!ucl_object_iterate_safe(it, true).is_null());
+        ucl_object_iterate_free(it);
+
+        ucl_object_unref(obj);
+    }
+}
+
+#[test]
+fn lookup() {
+    unsafe {
+        let obj = parse("a { b { c = 1 } }");
+
+        let a = ucl_object_find_keyl(obj, b"ab".as_ptr() as *const c_char, 1);
+        assert_eq!(CStr::from_ptr(ucl_object_key(a)).to_bytes(), b"a");
+        let mut len = 0;
+        assert!(!ucl_object_keyl(a, &mut len).is_null());
+        assert_eq!(len, 1);
+
+        let c = ucl_lookup_path(obj, b"a.b.c\0".as_ptr() as *const c_char);
+        assert_eq!(ucl_object_toint(c), 1);
+        let c = ucl_lookup_path_char(obj, b"a/b/c\0".as_ptr() as *const c_char, b'/' as c_char);
+        assert_eq!(ucl_object_toint(c), 1);
+
+        ucl_object_unref(obj);
+    }
+}
+
+#[test]
+fn refcount_and_priorities() {
+    unsafe {
+        let obj = ucl_object_fromint(1);
+        let other = ucl_object_ref(obj);
+        assert_eq!((*obj).ref_, 2);
+        ucl_object_unref(other);
+
+        ucl_object_set_priority(obj, 7);
+        assert_eq!(ucl_object_get_priority(obj), 7);
+        ucl_object_unref(obj);
+
+        let arr = parse("[1, 3, 2]");
+        ucl_object_array_sort(arr, Some(reverse));
+        assert_eq!(emit(arr), "[3,2,1]");
+        ucl_object_unref(arr);
+    }
+}
+
+#[test]
+fn schema() {
+    unsafe {
+        let schema = parse(r#"{ "type": "object", "required": ["a"] }"#);
+        let valid = parse("a = 1");
+        let invalid = parse("b = 1");
+        let mut err: ucl_schema_error = mem::zeroed();
+
+        assert!(ucl_object_validate(schema, valid, &mut err));
+        assert!(!ucl_object_validate_root(schema, invalid, invalid, &mut err));
+        assert_eq!(err.code as c_int, ucl_schema_error_code::UCL_SCHEMA_MISSING_PROPERTY as c_int);
+        assert!(ucl_object_validate_root_ext(schema, valid, valid, ptr::null_mut(), &mut err));
+
+        for obj in &[schema, valid, invalid] {
+            ucl_object_unref(*obj);
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Structural difference between two `Object` trees.
+//!
+//! Paths are built from keys and array indices delimited by `.` (dot), same as in
+//! `Object::fetch_path`. Values are rendered using `Emitter::JSONCompact`.
+//!
+//! Values stored under non-unique keys (implicit arrays) are compared element-wise and their
+//! elements are addressed by index, just like explicit arrays. Priorities are not compared, as
+//! parser already resolves them and only effective values matter.
+
+use object::{
+    Emitter,
+    Object,
+    Type
+};
+use Result;
+
+use std::collections::HashMap;
+
+/// Kind of change.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Kind {
+    Added,
+    Removed,
+    Modified
+}
+
+/// Single change between two trees.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Change {
+    /// Path to changed value
+    pub path: String,
+    /// Kind of change
+    pub kind: Kind,
+    /// Old value, `None` if value was added
+    pub old: Option<String>,
+    /// New value, `None` if value was removed
+    pub new: Option<String>
+}
+
+/// Compute list of changes needed to turn `old` into `new`.
+///
+/// Keys that are not valid UTF-8 are matched by their bytes and shown in paths with invalid
+/// sequences replaced. Fails with `Error::Emit` if changed value cannot be rendered.
+///
+/// # Examples
+///
+/// ```rust
+/// let old = ucl::Parser::new().parse("a = 1; b { c = 2; }").unwrap();
+/// let new = ucl::Parser::new().parse("b { c = 3; } d = lol").unwrap();
+///
+/// let changes = ucl::diff(&old, &new).unwrap();
+///
+/// assert_eq!(changes.len(), 3);
+/// assert_eq!(changes[0].path, "a");
+/// assert_eq!(changes[0].kind, ucl::diff::Kind::Removed);
+/// assert_eq!(changes[1].path, "b.c");
+/// assert_eq!(changes[1].old, Some("2".to_string()));
+/// assert_eq!(changes[1].new, Some("3".to_string()));
+/// assert_eq!(changes[2].path, "d");
+/// assert_eq!(changes[2].new, Some("\"lol\"".to_string()));
+/// ```
+pub fn diff(old: &Object, new: &Object) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    diff_values("", old, new, &mut changes)?;
+
+    Ok(changes)
+}
+
+fn diff_values(path: &str, old: &Object, new: &Object, changes: &mut Vec<Change>) -> Result<()> {
+    if !old.is_implicit_array() && !new.is_implicit_array() {
+        return diff_single(path, old, new, changes)
+    }
+
+    let old: Vec<_> = old.values().collect();
+    let new: Vec<_> = new.values().collect();
+
+    diff_lists(path, &old, &new, changes)
+}
+
+fn diff_single(path: &str, old: &Object, new: &Object, changes: &mut Vec<Change>) -> Result<()> {
+    match (old.get_type(), new.get_type()) {
+        (Type::Object, Type::Object) => {
+            let old: Vec<_> = old.iter().collect();
+            let new: Vec<_> = new.iter().collect();
+            let old_keys = keys(&old);
+            let new_keys = keys(&new);
+
+            for val in &old {
+                let key = val.key_bytes().unwrap_or_default();
+                let path = join(path, &String::from_utf8_lossy(key));
+
+                match new_keys.get(key) {
+                    Some(other) => diff_values(&path, val, other, changes)?,
+                    None => changes.push(Change::removed(path, val)?)
+                }
+            }
+
+            for val in &new {
+                let key = val.key_bytes().unwrap_or_default();
+
+                if !old_keys.contains_key(key) {
+                    changes.push(Change::added(join(path, &String::from_utf8_lossy(key)), val)?)
+                }
+            }
+        },
+        (Type::Array, Type::Array) => {
+            let old: Vec<_> = old.iter().collect();
+            let new: Vec<_> = new.iter().collect();
+
+            diff_lists(path, &old, &new, changes)?;
+        },
+        _ => {
+            let (old, new) = (render(old)?, render(new)?);
+
+            if old != new {
+                changes.push(Change {
+                    path: path.to_string(),
+                    kind: Kind::Modified,
+                    old: Some(old),
+                    new: Some(new)
+                })
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn diff_lists(path: &str, old: &[Object], new: &[Object], changes: &mut Vec<Change>) -> Result<()> {
+    for (idx, (old, new)) in old.iter().zip(new).enumerate() {
+        diff_values(&join(path, &idx.to_string()), old, new, changes)?;
+    }
+
+    for (idx, val) in old.iter().enumerate().skip(new.len()) {
+        changes.push(Change::removed(join(path, &idx.to_string()), val)?)
+    }
+
+    for (idx, val) in new.iter().enumerate().skip(old.len()) {
+        changes.push(Change::added(join(path, &idx.to_string()), val)?)
+    }
+
+    Ok(())
+}
+
+impl Change {
+    fn added(path: String, val: &Object) -> Result<Self> {
+        Ok(Change { path: path, kind: Kind::Added, old: None, new: Some(render(val)?) })
+    }
+
+    fn removed(path: String, val: &Object) -> Result<Self> {
+        Ok(Change { path: path, kind: Kind::Removed, old: Some(render(val)?), new: None })
+    }
+}
+
+/// Index entries of object by raw key.
+fn keys(entries: &[Object]) -> HashMap<&[u8], &Object> {
+    entries.iter().map(|val| (val.key_bytes().unwrap_or_default(), val)).collect()
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn render(obj: &Object) -> Result<String> {
+    let values = obj.values()
+        .map(|val| Emitter::JSONCompact.emit(&val))
+        .collect::<Result<Vec<String>>>()?;
+
+    if values.len() == 1 {
+        Ok(values.into_iter().next().unwrap_or_default())
+    } else {
+        Ok(format!("[{}]", values.join(",")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Error;
+    use Parser;
+
+    fn parse(s: &str) -> Object {
+        Parser::new().parse(s).unwrap()
+    }
+
+    #[test]
+    fn no_changes() {
+        let doc = "a = 1; b { c = [1, 2]; d = lol; }";
+
+        assert!(diff(&parse(doc), &parse(doc)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn modified_type() {
+        let changes = diff(&parse("a = 1"), &parse("a = \"1\"")).unwrap();
+
+        assert_eq!(changes, vec![Change {
+            path: "a".to_string(),
+            kind: Kind::Modified,
+            old: Some("1".to_string()),
+            new: Some("\"1\"".to_string())
+        }]);
+    }
+
+    #[test]
+    fn modified_float() {
+        let changes = diff(&parse("a = 0.5"), &parse("a = 0.7")).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, Kind::Modified);
+    }
+
+    #[test]
+    fn arrays() {
+        let changes = diff(&parse("a = [1, 2, 3]"), &parse("a = [1, 4]")).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].path, "a.1");
+        assert_eq!(changes[0].kind, Kind::Modified);
+        assert_eq!(changes[1].path, "a.2");
+        assert_eq!(changes[1].kind, Kind::Removed);
+    }
+
+    #[test]
+    fn implicit_arrays() {
+        let changes = diff(&parse("a = 1; a = 2;"), &parse("a = 1; a = 2; a = 3;")).unwrap();
+
+        assert_eq!(changes, vec![Change {
+            path: "a.2".to_string(),
+            kind: Kind::Added,
+            old: None,
+            new: Some("3".to_string())
+        }]);
+    }
+
+    #[test]
+    fn non_utf8_keys() {
+        use libc::c_char;
+        use libucl_sys::*;
+
+        let with_keys = |first: i64, second: i64| {
+            let obj = parse("a = 1");
+            for &(key, val) in &[(&b"\xff"[..], first), (&b"\xfe"[..], second)] {
+                unsafe {
+                    let key_ptr = key.as_ptr() as *const c_char;
+                    assert!(ucl_object_insert_key(obj.as_ptr() as *mut _, ucl_object_fromint(val), key_ptr, key.len(), true));
+                }
+            }
+            obj
+        };
+
+        assert!(diff(&with_keys(1, 2), &with_keys(1, 2)).unwrap().is_empty());
+
+        let changes = diff(&with_keys(1, 2), &with_keys(1, 3)).unwrap();
+        assert_eq!(changes, vec![Change {
+            path: "\u{fffd}".to_string(),
+            kind: Kind::Modified,
+            old: Some("2".to_string()),
+            new: Some("3".to_string())
+        }]);
+    }
+
+    #[test]
+    fn emit_error() {
+        let mut old = parse("a = 1");
+        let mut new = parse("a = 1");
+        old.pointer_mut("/a").unwrap().set(&b"\xff"[..]).unwrap();
+        new.pointer_mut("/a").unwrap().set(&b"\xfe"[..]).unwrap();
+
+        match diff(&old, &new) {
+            Err(Error::Emit(_)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn implicit_array_replaced_by_value() {
+        let changes = diff(&parse("a = 1; a = 2;"), &parse("a = 1;")).unwrap();
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "a.1");
+        assert_eq!(changes[0].kind, Kind::Removed);
+    }
+}
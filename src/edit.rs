@@ -0,0 +1,451 @@
+//! Format preserving editing of UCL documents.
+//!
+//! `Document` keeps original text of configuration and rewrites only values that are changed,
+//! so formatting, ordering, comments and number suffixes of remaining values stay intact.
+//!
+//! Paths use selector syntax (see `ucl::object::query`) without `*`, as they address single
+//! value: keys delimited by `.` (dot), where keys containing dots are quoted or escaped with `\`
+//! (i.e. `"a.b"` or `a\.b`), and array elements addressed by `[n]` or by index used as a key
+//! (i.e. `list[1]` or `list.1`). When key is repeated (implicit array), last occurrence is
+//! edited. Macros are kept verbatim and are not expanded, so values coming from included
+//! documents cannot be edited.
+
+use error::Error;
+use location::{
+    self,
+    Entry,
+    Source
+};
+use object::Object;
+use object::query::{
+    Selector,
+    Step
+};
+use parser::{
+    self,
+    Parser
+};
+use Result;
+
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+const INDENT: &str = "    ";
+
+/// Editable UCL document.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut doc = ucl::edit::Document::parse("# limits\nsection {\n    number = 10k; # soft\n}\n").unwrap();
+///
+/// doc.set("section.number", "20k").unwrap();
+///
+/// assert_eq!(doc.to_string(), "# limits\nsection {\n    number = 20k; # soft\n}\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    text: String
+}
+
+impl Document {
+    /// Parse document from string.
+    ///
+    /// Fails if string is not valid UCL document.
+    pub fn parse<T: AsRef<str>>(text: T) -> Result<Self> {
+        let text = text.as_ref().to_string();
+        validate(&text)?;
+
+        Ok(Document { text: text })
+    }
+
+    /// Load document from file at given `Path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path.as_ref()).map_err(|err| Error::io(path.as_ref(), &err))?;
+
+        Document::parse(text)
+    }
+
+    /// Save document to file at given `Path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path.as_ref(), &self.text).map_err(|err| Error::io(path.as_ref(), &err))
+    }
+
+    /// Return current text of document.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Parse current text of document into `Object`.
+    pub fn object(&self) -> Result<Object> {
+        validate(&self.text)
+    }
+
+    /// Set value at given path.
+    ///
+    /// `value` is UCL text of new value, i.e. `20k`, `"string"` or `{ a = 1; }`. Existing value
+    /// text is replaced in place. Missing key is appended at the end of its parent object, which
+    /// must already exist.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut doc = ucl::edit::Document::parse("a = 1;\nb = 2;\n").unwrap();
+    ///
+    /// doc.set("c", "\"three\"").unwrap();
+    ///
+    /// assert_eq!(doc.as_str(), "a = 1;\nb = 2;\nc = \"three\";\n");
+    /// ```
+    pub fn set<P: AsRef<str>, V: AsRef<str>>(&mut self, path: P, value: V) -> Result<()> {
+        let path = path.as_ref();
+        let value = value.as_ref().trim();
+        validate_value(value)?;
+
+        let entries = self.entries();
+        let steps = steps(path)?;
+
+        if let Some(&Entry::Value { value: ref range, .. }) = lookup(&entries, &steps) {
+            return self.splice(range.clone(), value)
+        }
+
+        // only keys can be added, array elements must exist
+        let (key, parent) = match steps.split_last() {
+            Some((&Step::Key(ref key), parent)) => (key, parent),
+            _ => return Err(Error::NotFound(path.to_string()))
+        };
+
+        let (siblings, open) = if parent.is_empty() {
+            let open = self.text.trim_start().starts_with('{');
+            let start = self.text.len() - self.text.trim_start().len();
+
+            (&entries[..], if open { Some(start) } else { None })
+        } else {
+            match lookup(&entries, parent) {
+                Some(&Entry::Value { ref children, value: ref range, .. })
+                    if self.text[range.clone()].starts_with('{') => (&children[..], Some(range.start)),
+                _ => return Err(Error::NotFound(path.to_string()))
+            }
+        };
+
+        let last = siblings.iter().filter_map(|entry| match *entry {
+            Entry::Value { start, ref value, .. } => Some((start, value.end)),
+            Entry::Include { .. } => None
+        }).last();
+
+        let statement = format!("{} = {};", quote(key), value);
+
+        match (last, open) {
+            (Some((start, end)), _) => {
+                let end = self.skip_comment(self.skip_separator(end));
+                let text = format!("\n{}{}", self.indent(start), statement);
+
+                self.splice(end..end, &text)
+            },
+            (None, Some(open)) => {
+                let indent = self.indent(open);
+                let text = format!("\n{}{}{}\n{}", indent, INDENT, statement, indent);
+
+                self.splice(open + 1..open + 1, &text)
+            },
+            (None, None) => {
+                let end = self.text.len();
+                let sep = if self.text.is_empty() || self.text.ends_with('\n') { "" } else { "\n" };
+                let text = format!("{}{}\n", sep, statement);
+
+                self.splice(end..end, &text)
+            }
+        }
+    }
+
+    /// Remove value at given path together with its key, separator and comment following it on
+    /// the same line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut doc = ucl::edit::Document::parse("a = 1;\nb = 2;\n").unwrap();
+    ///
+    /// doc.remove("a").unwrap();
+    ///
+    /// assert_eq!(doc.as_str(), "b = 2;\n");
+    /// ```
+    pub fn remove<P: AsRef<str>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let entries = self.entries();
+        let steps = steps(path)?;
+
+        let (start, end) = match lookup(&entries, &steps) {
+            Some(&Entry::Value { start, ref value, .. }) => (start, self.skip_comment(self.skip_separator(value.end))),
+            _ => return Err(Error::NotFound(path.to_string()))
+        };
+
+        let bytes = self.text.as_bytes();
+        let line_start = self.text[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        let mut line_end = end;
+        while line_end < bytes.len() && (bytes[line_end] == b' ' || bytes[line_end] == b'\t') {
+            line_end += 1;
+        }
+
+        // drop whole line if statement was the only thing on it
+        let blank_before = self.text[line_start..start].trim().is_empty();
+        let range = match bytes.get(line_end) {
+            Some(&b'\n') if blank_before => line_start..line_end + 1,
+            None if blank_before => line_start..line_end,
+            _ => start..line_end
+        };
+
+        self.splice(range, "")
+    }
+
+    fn entries(&self) -> Vec<Entry> {
+        location::scan(&Source {
+            file: None,
+            data: self.text.as_bytes().to_vec(),
+            priority: 0
+        })
+    }
+
+    fn splice(&mut self, range: Range<usize>, with: &str) -> Result<()> {
+        let mut text = self.text.clone();
+        text.replace_range(range, with);
+        validate(&text)?;
+
+        self.text = text;
+
+        Ok(())
+    }
+
+    fn skip_separator(&self, pos: usize) -> usize {
+        match self.text.as_bytes().get(pos) {
+            Some(&b';') | Some(&b',') => pos + 1,
+            _ => pos
+        }
+    }
+
+    /// Skip comment ending the line after `pos`, so it stays with the statement before it.
+    fn skip_comment(&self, pos: usize) -> usize {
+        let rest = &self.text[pos..];
+        let line = rest[..rest.find('\n').unwrap_or(rest.len())].trim_end();
+        let comment = line.trim_start();
+
+        if comment.starts_with('#') || (comment.starts_with("/*") && comment.ends_with("*/")) {
+            pos + line.len()
+        } else {
+            pos
+        }
+    }
+
+    fn indent(&self, pos: usize) -> &str {
+        let line_start = self.text[..pos].rfind('\n').map_or(0, |pos| pos + 1);
+        let line = &self.text[line_start..pos];
+
+        &line[..line.len() - line.trim_start().len()]
+    }
+}
+
+impl FromStr for Document {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Document::parse(s)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(&self.text)
+    }
+}
+
+fn validate(text: &str) -> Result<Object> {
+    Parser::with_flags(parser::DISABLE_MACRO).parse(text)
+}
+
+fn validate_value(value: &str) -> Result<()> {
+    let obj = validate(&format!("value = {}\n", value))?;
+    let single = obj.iter().count() == 1 && obj.fetch("value").map_or(false, |val| !val.is_implicit_array());
+
+    if value.is_empty() || !single {
+        return Err(Error::Syntax(format!("invalid value: {}", value)))
+    }
+
+    Ok(())
+}
+
+/// Parse path into selector steps, which must address single value.
+fn steps(path: &str) -> Result<Vec<Step>> {
+    let selector = Selector::parse(path)?;
+
+    if selector.steps().contains(&Step::All) {
+        return Err(Error::InvalidSelector(format!("path must not contain `*`: {}", path)))
+    }
+
+    Ok(selector.steps().to_vec())
+}
+
+fn lookup<'a>(entries: &'a [Entry], path: &[Step]) -> Option<&'a Entry> {
+    let (step, rest) = path.split_first()?;
+    let keyed = entries.iter().any(|entry| key(entry).is_some());
+
+    let candidates: Vec<&Entry> = match *step {
+        Step::Key(ref segment) if keyed => entries.iter().filter(|entry| key(entry) == Some(segment)).collect(),
+        Step::Key(ref segment) => element(entries, segment.parse().ok()),
+        Step::Index(idx) if !keyed => element(entries, Some(idx)),
+        _ => Vec::new()
+    };
+
+    candidates.into_iter().rev().filter_map(|entry| match *entry {
+        _ if rest.is_empty() => Some(entry),
+        Entry::Value { ref children, .. } => lookup(children, rest),
+        Entry::Include { .. } => None
+    }).next()
+}
+
+/// Return `idx`-th element of array.
+fn element(entries: &[Entry], idx: Option<usize>) -> Vec<&Entry> {
+    let mut values = entries.iter().filter(|entry| match **entry {
+        Entry::Value { .. } => true,
+        Entry::Include { .. } => false
+    });
+
+    idx.and_then(|idx| values.nth(idx)).into_iter().collect()
+}
+
+fn key(entry: &Entry) -> Option<&str> {
+    match *entry {
+        Entry::Value { key: Some(ref key), .. } => Some(key),
+        _ => None
+    }
+}
+
+fn quote(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if bare {
+        key.to_string()
+    } else {
+        format!("\"{}\"", key.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static DOC: &str = "# main config\nparam = value;\nsection {\n    flag = true; # enabled\n    number = 10k;\n    list = [1, 2, 3]\n}\n";
+
+    #[test]
+    fn set_preserves_formatting() {
+        let mut doc = Document::parse(DOC).unwrap();
+        doc.set("section.number", "20k").unwrap();
+
+        assert_eq!(doc.as_str(), DOC.replace("10k", "20k"));
+        assert_eq!(doc.object().unwrap().fetch_path("section.number").unwrap().as_int(), Some(20000));
+    }
+
+    #[test]
+    fn set_array_element() {
+        let mut doc = Document::parse(DOC).unwrap();
+        doc.set("section.list.1", "\"two\"").unwrap();
+
+        assert_eq!(doc.as_str(), DOC.replace("[1, 2, 3]", "[1, \"two\", 3]"));
+    }
+
+    #[test]
+    fn set_named_section() {
+        let mut doc = Document::parse("upstream \"backend\" {\n  port = 80\n}\n").unwrap();
+        doc.set("upstream.backend.port", "8080").unwrap();
+
+        assert_eq!(doc.as_str(), "upstream \"backend\" {\n  port = 8080\n}\n");
+    }
+
+    #[test]
+    fn insert_key() {
+        let mut doc = Document::parse(DOC).unwrap();
+        doc.set("section.name", "\"new\"").unwrap();
+
+        let expected = DOC.replace("[1, 2, 3]\n", "[1, 2, 3]\n    name = \"new\";\n");
+        assert_eq!(doc.as_str(), expected);
+    }
+
+    #[test]
+    fn insert_after_comment() {
+        let mut doc = Document::parse("b {\n  c = 2 /* two */\n}\na = 1; # note\n").unwrap();
+        doc.set("d", "3").unwrap();
+        doc.set("b.e", "4").unwrap();
+
+        assert_eq!(doc.as_str(), "b {\n  c = 2 /* two */\n  e = 4;\n}\na = 1; # note\nd = 3;\n");
+    }
+
+    #[test]
+    fn insert_into_empty_object() {
+        let mut doc = Document::parse("a {}\n").unwrap();
+        doc.set("a.b", "1").unwrap();
+
+        assert_eq!(doc.as_str(), "a {\n    b = 1;\n}\n");
+    }
+
+    #[test]
+    fn remove_key() {
+        let mut doc = Document::parse(DOC).unwrap();
+        doc.remove("section.flag").unwrap();
+
+        assert_eq!(doc.as_str(), DOC.replace("    flag = true; # enabled\n", ""));
+    }
+
+    #[test]
+    fn remove_block_comment() {
+        let mut doc = Document::parse("a = 1; /* one */\nb = 2;\n").unwrap();
+        doc.remove("a").unwrap();
+
+        assert_eq!(doc.as_str(), "b = 2;\n");
+    }
+
+    #[test]
+    fn remove_line() {
+        let mut doc = Document::parse(DOC).unwrap();
+        doc.remove("param").unwrap();
+
+        assert_eq!(doc.as_str(), DOC.replace("param = value;\n", ""));
+    }
+
+    #[test]
+    fn escaped_path() {
+        let mut doc = Document::parse("\"a.b\" = 1;\nlist = [1, 2];\n").unwrap();
+        doc.set(r"a\.b", "2").unwrap();
+        doc.set("\"c.d\"", "3").unwrap();
+        doc.set("list[1]", "4").unwrap();
+
+        assert_eq!(doc.as_str(), "\"a.b\" = 2;\nlist = [1, 4];\n\"c.d\" = 3;\n");
+
+        doc.remove("'c.d'").unwrap();
+        assert_eq!(doc.as_str(), "\"a.b\" = 2;\nlist = [1, 4];\n");
+
+        match doc.set("list[*]", "5") {
+            Err(Error::InvalidSelector(_)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn invalid_value() {
+        let mut doc = Document::parse(DOC).unwrap();
+
+        assert!(doc.set("param", "{ unclosed").is_err());
+        assert!(doc.set("param", "1; other = 2").is_err());
+        assert_eq!(doc.as_str(), DOC);
+    }
+
+    #[test]
+    fn missing_parent() {
+        let mut doc = Document::parse(DOC).unwrap();
+
+        match doc.set("missing.key", "1") {
+            Err(Error::NotFound(path)) => assert_eq!(path, "missing.key"),
+            _ => panic!("expected NotFound")
+        }
+    }
+}
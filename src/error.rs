@@ -1,26 +1,82 @@
 use libucl_sys::ucl_error_t;
 
+use std::convert;
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{
+    Path,
+    PathBuf
+};
+
 #[derive(Clone, Debug)]
 pub enum Error {
+    /// No error, returned by libucl only
     Ok,
+    /// Syntax error in parsed document
     Syntax(String),
-    Io,
+    /// I/O error on given path with `errno`, both are unknown for errors reported by libucl
+    Io(Option<PathBuf>, Option<i32>),
+    /// Invalid state of libucl parser
     State,
+    /// Too deeply nested document
     Nested,
+    /// Unpaired element reported by libucl, i.e. signature without signed data
+    Unpaired(String),
+    /// Error in macro
     Macro,
+    /// Internal libucl error
     Internal,
+    /// Signature verification error
     SSL,
+    /// Value cannot be converted
+    Conversion(String),
+    /// Operation cannot be applied to value of this type
+    TypeMismatch(String),
+    /// Document exceeds `Limits::size`
+    TooLarge(usize),
+    /// Document exceeds `Limits::depth`
+    TooDeep(usize),
+    /// Document exceeds `Limits::objects`
+    TooManyObjects(usize),
+    /// Included document cannot be loaded
+    Include(String),
+    /// Path not found in document
+    NotFound(String),
+    /// Invalid query selector
+    InvalidSelector(String),
+    /// URL include requested from libucl built without URL support
+    UrlsDisabled(String),
+    /// Document rejected by validation
+    Validation(String),
+    /// Error in given file
+    File(PathBuf, Box<Error>),
+    /// libucl failed to allocate object
+    Alloc,
+    /// Object cannot be emitted
+    Emit(String),
+    /// Invalid key or variable name
+    InvalidKey(String),
+    /// Source locations cannot be matched with parsed values
+    Untracked(String),
+    /// Unknown error
     Other
 }
 
 impl Error {
+    /// Create `Error::Io` for operation on given path.
+    pub fn io(path: &Path, err: &io::Error) -> Self {
+        Error::Io(Some(path.to_path_buf()), err.raw_os_error())
+    }
+
     pub fn from_code(num: i32, desc: String) -> Self {
         match num {
             _ if num == ucl_error_t::UCL_EOK       as i32 => Error::Ok,
             _ if num == ucl_error_t::UCL_ESYNTAX   as i32 => Error::Syntax(desc),
-            _ if num == ucl_error_t::UCL_EIO       as i32 => Error::Io,
+            _ if num == ucl_error_t::UCL_EIO       as i32 => Error::Io(None, None),
             _ if num == ucl_error_t::UCL_ESTATE    as i32 => Error::State,
             _ if num == ucl_error_t::UCL_ENESTED   as i32 => Error::Nested,
+            _ if num == ucl_error_t::UCL_EUNPAIRED as i32 => Error::Unpaired(desc),
             _ if num == ucl_error_t::UCL_EMACRO    as i32 => Error::Macro,
             _ if num == ucl_error_t::UCL_EINTERNAL as i32 => Error::Internal,
             _ if num == ucl_error_t::UCL_ESSL      as i32 => Error::SSL,
@@ -28,3 +84,73 @@ impl Error {
         }
     }
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Ok => write!(fmt, "no error"),
+            Error::Syntax(ref desc) => write!(fmt, "syntax error: {}", desc),
+            Error::Io(ref path, errno) => {
+                write!(fmt, "I/O error")?;
+                if let Some(ref path) = *path { write!(fmt, " on {}", path.display())? }
+                if let Some(errno) = errno { write!(fmt, ": {}", io::Error::from_raw_os_error(errno))? }
+                Ok(())
+            },
+            Error::State => write!(fmt, "invalid parser state"),
+            Error::Nested => write!(fmt, "document is nested too deeply"),
+            Error::Unpaired(ref desc) => write!(fmt, "unpaired element: {}", desc),
+            Error::Macro => write!(fmt, "macro error"),
+            Error::Internal => write!(fmt, "internal libucl error"),
+            Error::SSL => write!(fmt, "signature verification failed"),
+            Error::Conversion(ref desc) => write!(fmt, "conversion error: {}", desc),
+            Error::TypeMismatch(ref desc) => write!(fmt, "type mismatch: {}", desc),
+            Error::TooLarge(limit) => write!(fmt, "document is larger than {} bytes", limit),
+            Error::TooDeep(limit) => write!(fmt, "document is nested deeper than {} levels", limit),
+            Error::TooManyObjects(limit) => write!(fmt, "document has more than {} values", limit),
+            Error::Include(ref desc) => write!(fmt, "include error: {}", desc),
+            Error::NotFound(ref path) => write!(fmt, "not found: {}", path),
+            Error::InvalidSelector(ref desc) => write!(fmt, "invalid selector: {}", desc),
+            Error::UrlsDisabled(ref desc) => write!(fmt, "URL support is disabled: {}", desc),
+            Error::Validation(ref desc) => write!(fmt, "validation failed: {}", desc),
+            Error::File(ref path, ref err) => write!(fmt, "{}: {}", path.display(), err),
+            Error::Alloc => write!(fmt, "allocation failed"),
+            Error::Emit(ref desc) => write!(fmt, "cannot emit object: {}", desc),
+            Error::InvalidKey(ref key) => write!(fmt, "invalid key: {:?}", key),
+            Error::Untracked(ref desc) => write!(fmt, "cannot track source locations: {}", desc),
+            Error::Other => write!(fmt, "unknown error")
+        }
+    }
+}
+
+impl From<convert::Infallible> for Error {
+    fn from(err: convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::File(_, ref err) => Some(&**err),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_code() {
+        match Error::from_code(ucl_error_t::UCL_EUNPAIRED as i32, "unpaired".to_string()) {
+            Error::Unpaired(ref desc) => assert_eq!(desc, "unpaired"),
+            err => panic!("unexpected error: {:?}", err)
+        }
+
+        match Error::from_code(-1, String::new()) {
+            Error::Other => (),
+            err => panic!("unexpected error: {:?}", err)
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Custom resolution of `.include` macros.
+//!
+//! By default libucl loads included files from the filesystem (and URLs when built with
+//! `--enable-urls`). Setting `IncludeResolver` with `Parser::set_include_resolver` or adding
+//! search path with `Parser::add_include_path` replaces libucl's `.include` and `.try_include`
+//! macros with resolution done in Rust. Only documents loaded this way can be scanned for
+//! locations by `Parser::track_locations`.
+//!
+//! Supported macro parameters are `priority` (between 0 and 15) and `try`. Other parameters,
+//! globs, URLs and signatures are not supported by custom resolution. Documents including
+//! themselves, directly or not, and includes nested deeper than 16 levels fail with
+//! `Error::Include`.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{
+    self,
+    Read
+};
+use std::path::{
+    Path,
+    PathBuf
+};
+
+/// Source of included documents.
+pub trait IncludeResolver {
+    /// Resolve `path` given in `.include` macro using `search_paths`.
+    ///
+    /// Returns `None` when document cannot be found.
+    fn resolve(&self, path: &str, search_paths: &[PathBuf]) -> Option<PathBuf>;
+
+    /// Load content of resolved document.
+    fn load(&self, path: &Path) -> io::Result<Vec<u8>>;
+}
+
+/// Return paths that should be checked for given include.
+///
+/// Absolute paths are returned as is. Relative paths are joined with each of search paths and
+/// at the end path itself is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::PathBuf;
+///
+/// let paths = ucl::include::candidates("a.conf", &[PathBuf::from("/etc/app")]);
+///
+/// assert_eq!(paths, vec![PathBuf::from("/etc/app/a.conf"), PathBuf::from("a.conf")]);
+/// ```
+pub fn candidates(path: &str, search_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let path = Path::new(path);
+
+    if path.is_absolute() {
+        return vec![path.to_path_buf()]
+    }
+
+    search_paths.iter()
+        .map(|dir| dir.join(path))
+        .chain(Some(path.to_path_buf()))
+        .collect()
+}
+
+/// Resolver loading documents from filesystem.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct FsResolver;
+
+impl IncludeResolver for FsResolver {
+    fn resolve(&self, path: &str, search_paths: &[PathBuf]) -> Option<PathBuf> {
+        candidates(path, search_paths).into_iter().find(|path| path.is_file())
+    }
+
+    fn load(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        File::open(path)?.read_to_end(&mut buf)?;
+
+        Ok(buf)
+    }
+}
+
+/// Resolver loading documents from memory.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use std::path::PathBuf;
+///
+/// let mut files = HashMap::new();
+/// files.insert(PathBuf::from("/etc/app/base.conf"), b"a = 1".to_vec());
+///
+/// let mut parser = ucl::Parser::new();
+/// parser.set_include_resolver(files);
+/// parser.add_include_path("/etc/app");
+///
+/// let doc = parser.parse(".include \"base.conf\"\nb = 2").unwrap();
+///
+/// assert_eq!(doc.fetch("a").unwrap().as_int(), Some(1));
+/// ```
+impl IncludeResolver for HashMap<PathBuf, Vec<u8>> {
+    fn resolve(&self, path: &str, search_paths: &[PathBuf]) -> Option<PathBuf> {
+        candidates(path, search_paths).into_iter().find(|path| self.contains_key(path))
+    }
+
+    fn load(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+}
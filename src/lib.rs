@@ -91,14 +91,22 @@
 extern crate libucl_sys;
 extern crate libc;
 #[macro_use] extern crate bitflags;
+#[cfg(feature = "serde_json")] extern crate serde_json;
 
 pub use error::Error;
 pub use parser::Parser;
 pub use object::Object;
+pub use diff::diff;
+pub use location::Location;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 mod utils;
+mod location;
 pub mod error;
 pub mod parser;
 pub mod object;
+pub mod diff;
+pub mod include;
+pub mod edit;
+pub mod reload;
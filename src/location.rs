@@ -0,0 +1,747 @@
+//! Source locations of parsed objects.
+//!
+//! libucl does not keep positions of parsed values, so documents are scanned once more on the
+//! Rust side. Scanner recognises statements (keys, array elements, `.include`, `.try_include`,
+//! `.load` and `.priority` macros) and matches them with objects in parsed tree in the same
+//! order as libucl adds them. Values added by other macros (i.e. `.inherit`) have no location.
+//!
+//! Matching fails with `Error::Untracked` instead of guessing when statements cannot be paired
+//! with values, i.e. when number of values stored under a key differs from number of statements
+//! (as with duplicate keys merged by libucl) or when document includes files loaded by libucl,
+//! which are not available for scanning.
+
+use libucl_sys::ucl_object_t;
+
+use error::Error;
+use object::{
+    Object,
+    Type
+};
+use parser::{
+    Flags,
+    LOWERCASE,
+    NO_IMPLICIT_ARRAYS
+};
+use Result;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Location of object in source document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// File containing object, `None` if object was parsed from string
+    pub file: Option<PathBuf>,
+    /// Line number, starting from 1
+    pub line: usize
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.file {
+            Some(ref file) => write!(fmt, "{}:{}", file.display(), self.line),
+            None => write!(fmt, "<string>:{}", self.line)
+        }
+    }
+}
+
+/// Locations of objects indexed by their address.
+///
+/// Values removed from the tree must be forgotten, as their addresses can be reused by new
+/// values once libucl frees them.
+#[derive(Default)]
+pub struct Table {
+    locations: RefCell<HashMap<usize, Location>>
+}
+
+impl Table {
+    /// Return location of given value.
+    pub fn get(&self, obj: *const ucl_object_t) -> Option<Location> {
+        self.locations.borrow().get(&(obj as usize)).cloned()
+    }
+
+    /// Forget locations of `obj` and all values nested in it.
+    pub fn forget(&self, obj: &Object) {
+        for val in obj.values() {
+            self.locations.borrow_mut().remove(&(val.as_ptr() as usize));
+
+            for child in val.iter() {
+                self.forget(&child);
+            }
+        }
+    }
+
+    fn insert(&mut self, obj: &Object, location: Location) {
+        self.locations.get_mut().insert(obj.as_ptr() as usize, location);
+    }
+}
+
+/// Document loaded by `.include` macro.
+pub struct Source {
+    pub file: Option<PathBuf>,
+    pub data: Vec<u8>,
+    pub priority: usize
+}
+
+/// Build locations table for `obj` parsed from `main` document.
+///
+/// `included` contains documents in order they were loaded by custom `.include` resolution,
+/// `None` for documents that were not found. It is `None` itself when includes are handled by
+/// libucl.
+pub fn locate(obj: &Object, main: &Source, included: Option<&[Option<Source>]>, flags: Flags) -> Result<Table> {
+    let mut next = 0;
+    let entries = expand(scan(main), included, &mut next)?;
+
+    if next < included.map_or(0, |included| included.len()) {
+        return Err(Error::Untracked("document includes more files than scanner found".to_string()))
+    }
+
+    let mut table = Table::default();
+    assign(obj, &entries, flags, &mut table)?;
+
+    Ok(table)
+}
+
+/// Scan statements of single document without expanding includes.
+pub fn scan(source: &Source) -> Vec<Entry> {
+    Scanner::new(source).document()
+}
+
+/// Statement found in document.
+pub enum Entry {
+    Value {
+        key: Option<String>,
+        location: Location,
+        priority: usize,
+        /// Offset of statement start (key or array element)
+        start: usize,
+        /// Offsets of value text
+        value: Range<usize>,
+        children: Vec<Entry>
+    },
+    Include {
+        location: Location,
+        /// Included path or URL
+        path: String,
+        /// Whether document fails to parse when included one cannot be loaded
+        required: bool
+    }
+}
+
+fn expand(entries: Vec<Entry>, included: Option<&[Option<Source>]>, next: &mut usize) -> Result<Vec<Entry>> {
+    let mut out = Vec::new();
+
+    for entry in entries {
+        match entry {
+            Entry::Include { location, .. } => {
+                let source = match included.and_then(|included| included.get(*next)) {
+                    Some(source) => source,
+                    None if included.is_none() => {
+                        return Err(Error::Untracked(format!("{}: files included by libucl cannot be scanned, \
+                                                             use custom include resolution", location)))
+                    },
+                    None => return Err(Error::Untracked(format!("{}: included file was not loaded", location)))
+                };
+                *next += 1;
+
+                if let Some(ref source) = *source {
+                    out.extend(expand(scan(source), included, next)?);
+                }
+            },
+            Entry::Value { key, location, priority, start, value, children } => {
+                let children = expand(children, included, next)?;
+                out.push(Entry::Value {
+                    key: key,
+                    location: location,
+                    priority: priority,
+                    start: start,
+                    value: value,
+                    children: children
+                });
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn assign(obj: &Object, entries: &[Entry], flags: Flags, table: &mut Table) -> Result<()> {
+    let values = entries.iter().filter_map(|entry| match *entry {
+        Entry::Value { ref key, ref location, priority, ref children, .. } => {
+            Some((key, location, priority, children))
+        },
+        Entry::Include { .. } => None
+    });
+
+    match obj.get_type() {
+        Type::Object => {
+            let values: Vec<_> = values.filter(|&(key, _, _, _)| key.is_some()).collect();
+
+            for val in obj.iter() {
+                let key = val.key().unwrap_or_default();
+                let same_key = |entry_key: &Option<String>| match *entry_key {
+                    // libucl lowercases ASCII letters only
+                    Some(ref entry_key) if flags.contains(LOWERCASE) => entry_key.to_ascii_lowercase() == key,
+                    Some(ref entry_key) => *entry_key == key,
+                    None => false
+                };
+
+                let candidates: Vec<_> = values.iter().filter(|&&(key, _, _, _)| same_key(key)).collect();
+                // only values with highest priority are kept by libucl
+                let max = candidates.iter().map(|&&(_, _, prio, _)| prio).max().unwrap_or(0);
+                let candidates: Vec<_> = candidates.into_iter().filter(|&&(_, _, prio, _)| prio == max).collect();
+
+                // values added by other macros have no statements
+                if candidates.is_empty() { continue }
+
+                // repeated keys are collected into explicit array instead of implicit one
+                if flags.contains(NO_IMPLICIT_ARRAYS) && candidates.len() > 1 && val.get_type() == Type::Array {
+                    unpaired(&key, val.iter().count(), &candidates.iter().map(|c| c.1).collect::<Vec<_>>())?;
+                    table.insert(&val, candidates[0].1.clone());
+
+                    for (elt, &&(_, location, _, children)) in val.iter().zip(&candidates) {
+                        table.insert(&elt, location.clone());
+                        assign(&elt, children, flags, table)?;
+                    }
+                    continue
+                }
+
+                unpaired(&key, val.values().count(), &candidates.iter().map(|c| c.1).collect::<Vec<_>>())?;
+                for (val, &&(_, location, _, children)) in val.values().zip(&candidates) {
+                    table.insert(&val, location.clone());
+                    assign(&val, children, flags, table)?;
+                }
+            }
+        },
+        Type::Array => {
+            let values: Vec<_> = values.collect();
+            if !values.is_empty() && values.len() != obj.iter().count() {
+                return Err(Error::Untracked(format!("{}: array has {} elements, but {} were found",
+                                                    values[0].1,
+                                                    obj.iter().count(),
+                                                    values.len())))
+            }
+
+            for (val, &(_, location, _, children)) in obj.iter().zip(&values) {
+                table.insert(&val, location.clone());
+                assign(&val, children, flags, table)?;
+            }
+        },
+        _ => ()
+    }
+
+    Ok(())
+}
+
+/// Fail if `count` values stored under `key` cannot be paired with statements at `locations`,
+/// i.e. when libucl merged or dropped some of them.
+fn unpaired(key: &str, count: usize, locations: &[&Location]) -> Result<()> {
+    if count == locations.len() { return Ok(()) }
+
+    Err(Error::Untracked(format!("{}: key {:?} has {} values, but {} statements were found",
+                                 locations[0],
+                                 key,
+                                 count,
+                                 locations.len())))
+}
+
+struct Scanner<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    line: usize,
+    file: &'a Option<PathBuf>,
+    priority: usize
+}
+
+impl<'a> Scanner<'a> {
+    fn new(source: &'a Source) -> Self {
+        Scanner {
+            buf: &source.data,
+            pos: 0,
+            line: 1,
+            file: &source.file,
+            priority: source.priority
+        }
+    }
+
+    fn document(&mut self) -> Vec<Entry> {
+        self.skip_ws(true);
+
+        match self.peek() {
+            Some(b'{') => { self.bump(); self.object(Some(b'}')) },
+            Some(b'[') => { self.bump(); self.array() },
+            _ => self.object(None)
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.buf.get(self.pos).cloned()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.buf.get(self.pos + offset).cloned()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let c = self.peek();
+        if c == Some(b'\n') { self.line += 1 }
+        if c.is_some() { self.pos += 1 }
+
+        c
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            file: self.file.clone(),
+            line: self.line
+        }
+    }
+
+    fn skip_ws(&mut self, newlines: bool) {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(b' '), _) | (Some(b'\t'), _) | (Some(b'\r'), _) => { self.bump(); },
+                (Some(b'\n'), _) if newlines => { self.bump(); },
+                (Some(b'#'), _) => {
+                    while self.peek().map_or(false, |c| c != b'\n') { self.bump(); }
+                },
+                (Some(b'/'), Some(b'*')) => self.skip_comment(),
+                _ => return
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        let mut depth = 0;
+        let mut quoted = false;
+
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                // comment markers inside quotes are ignored, same as in libucl
+                (Some(b'\\'), Some(_)) if quoted => { self.bump(); self.bump(); },
+                (Some(b'"'), _) => { self.bump(); quoted = !quoted },
+                (Some(_), _) if quoted => { self.bump(); },
+                (Some(b'/'), Some(b'*')) => { self.bump(); self.bump(); depth += 1 },
+                (Some(b'*'), Some(b'/')) => {
+                    self.bump();
+                    self.bump();
+                    depth -= 1;
+                    if depth == 0 { return }
+                },
+                (Some(_), _) => { self.bump(); },
+                (None, _) => return
+            }
+        }
+    }
+
+    fn object(&mut self, end: Option<u8>) -> Vec<Entry> {
+        let mut entries = Vec::new();
+
+        loop {
+            self.skip_ws(true);
+
+            match self.peek() {
+                None => break,
+                Some(c) if Some(c) == end => { self.bump(); break },
+                Some(b'}') | Some(b']') => { self.bump(); break },
+                Some(b';') | Some(b',') => { self.bump(); },
+                Some(b'.') => self.directive(&mut entries),
+                Some(_) => {
+                    let location = self.location();
+                    let start = self.pos;
+                    let key = self.key();
+
+                    self.skip_ws(true);
+                    if let Some(b'=') | Some(b':') = self.peek() {
+                        self.bump();
+                        self.skip_ws(true);
+                    }
+
+                    entries.push(self.value(Some(key), location, start));
+                }
+            }
+        }
+
+        entries
+    }
+
+    fn array(&mut self) -> Vec<Entry> {
+        let mut entries = Vec::new();
+
+        loop {
+            self.skip_ws(true);
+
+            match self.peek() {
+                None => break,
+                Some(b']') | Some(b'}') => { self.bump(); break },
+                Some(b';') | Some(b',') => { self.bump(); },
+                Some(_) => {
+                    let location = self.location();
+                    let start = self.pos;
+                    entries.push(self.value(None, location, start));
+                }
+            }
+        }
+
+        entries
+    }
+
+    fn value(&mut self, key: Option<String>, location: Location, start: usize) -> Entry {
+        let value = self.pos;
+        let (children, mut end) = match self.peek() {
+            Some(b'{') => { self.bump(); (self.object(Some(b'}')), self.pos) },
+            Some(b'[') => { self.bump(); (self.array(), self.pos) },
+            _ => {
+                let name = self.scalar();
+                // trailing whitespace and comments are not part of the value
+                let end = self.pos;
+                self.skip_ws(false);
+
+                match self.peek() {
+                    // named section, i.e. `section "name" { ... }`
+                    Some(b'{') | Some(b'"') | Some(b'\'') if key.is_some() => {
+                        let location = self.location();
+                        (vec![self.value(Some(name), location, value)], self.pos)
+                    },
+                    _ => (Vec::new(), end)
+                }
+            }
+        };
+
+        while end > value && is_space(self.buf[end - 1]) { end -= 1 }
+
+        Entry::Value {
+            key: key,
+            location: location,
+            priority: self.priority,
+            start: start,
+            value: value..end,
+            children: children
+        }
+    }
+
+    fn directive(&mut self, entries: &mut Vec<Entry>) {
+        let location = self.location();
+        let start = self.pos;
+        self.bump();
+
+        let name_start = self.pos;
+        while self.peek().map_or(false, |c| c.is_ascii_alphanumeric() || c == b'_') { self.bump(); }
+        let name = String::from_utf8_lossy(&self.buf[name_start..self.pos]).into_owned();
+
+        self.skip_ws(false);
+        let params = if self.peek() == Some(b'(') { self.params() } else { Vec::new() };
+        let param = |name: &str| params.iter().find(|&&(ref key, _)| key == name).map(|&(_, ref val)| val.clone());
+
+        let value = self.pos;
+        let arg = match self.peek() {
+            Some(b'{') => { self.bump(); self.object(Some(b'}')); String::new() },
+            _ => self.scalar()
+        };
+
+        match &*name {
+            "include" | "try_include" => entries.push(Entry::Include {
+                location: location,
+                path: arg,
+                required: name == "include" && param("try").map_or(true, |soft| soft != "true")
+            }),
+            // loaded file is stored under key given by parameter
+            "load" => if let Some(key) = param("key") {
+                entries.push(Entry::Value {
+                    key: Some(key),
+                    location: location,
+                    priority: param("priority").and_then(|prio| prio.parse().ok()).unwrap_or(self.priority),
+                    start: start,
+                    value: value..self.pos,
+                    children: Vec::new()
+                });
+            },
+            "priority" => {
+                if let Some(priority) = param("priority").unwrap_or(arg).trim().parse().ok() {
+                    self.priority = priority;
+                }
+            },
+            _ => ()
+        }
+    }
+
+    /// Scan macro parameters, i.e. `(key = "name", try = true)`.
+    fn params(&mut self) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        self.bump();
+
+        loop {
+            self.skip_ws(true);
+
+            match self.peek() {
+                None => break,
+                Some(b')') => { self.bump(); break },
+                Some(b',') | Some(b';') => { self.bump(); },
+                Some(_) => {
+                    let key = self.key();
+                    self.skip_ws(true);
+                    if let Some(b'=') | Some(b':') = self.peek() {
+                        self.bump();
+                        self.skip_ws(true);
+                    }
+
+                    let val = match self.peek() {
+                        Some(b'"') | Some(b'\'') => self.string(),
+                        _ => {
+                            let start = self.pos;
+                            while self.peek().map_or(false, |c| !is_key_end(c) && c != b')') { self.bump(); }
+                            String::from_utf8_lossy(&self.buf[start..self.pos]).into_owned()
+                        }
+                    };
+                    params.push((key, val));
+                }
+            }
+        }
+
+        self.skip_ws(false);
+        params
+    }
+
+    fn key(&mut self) -> String {
+        match self.peek() {
+            Some(b'"') | Some(b'\'') => self.string(),
+            _ => {
+                let start = self.pos;
+                while self.peek().map_or(false, |c| !is_key_end(c)) { self.bump(); }
+
+                if start == self.pos {
+                    // skip unexpected character
+                    self.bump();
+                }
+
+                String::from_utf8_lossy(&self.buf[start..self.pos]).into_owned()
+            }
+        }
+    }
+
+    fn scalar(&mut self) -> String {
+        match (self.peek(), self.peek_at(1)) {
+            (Some(b'"'), _) | (Some(b'\''), _) => self.string(),
+            (Some(b'<'), Some(b'<')) if self.heredoc_tag().is_some() => self.heredoc(),
+            _ => {
+                let start = self.pos;
+                loop {
+                    match (self.peek(), self.peek_at(1)) {
+                        (None, _) | (Some(b'/'), Some(b'*')) => break,
+                        (Some(c), _) if is_value_end(c) => break,
+                        _ => { self.bump(); }
+                    }
+                }
+
+                String::from_utf8_lossy(&self.buf[start..self.pos]).trim().to_string()
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let quote = self.bump();
+        let mut out = Vec::new();
+
+        loop {
+            match self.bump() {
+                None => break,
+                Some(b'\\') => match self.bump() {
+                    Some(b'n') => out.push(b'\n'),
+                    Some(b't') => out.push(b'\t'),
+                    Some(b'r') => out.push(b'\r'),
+                    Some(c) => out.push(c),
+                    None => break
+                },
+                c if c == quote => break,
+                Some(c) => out.push(c)
+            }
+        }
+
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// Return tag of heredoc starting at current position. libucl accepts only uppercase tags
+    /// followed by newline, other values starting with `<<` are ordinary strings.
+    fn heredoc_tag(&self) -> Option<&'a [u8]> {
+        let start = self.pos + 2;
+        let len = self.buf[start..].iter().take_while(|c| c.is_ascii_uppercase()).count();
+
+        match self.buf.get(start + len) {
+            Some(&b'\n') if len > 0 => Some(&self.buf[start..start + len]),
+            _ => None
+        }
+    }
+
+    fn heredoc(&mut self) -> String {
+        let tag = self.heredoc_tag().unwrap_or_default();
+        for _ in 0..tag.len() + 3 { self.bump(); }
+
+        let body = self.pos;
+        loop {
+            let line = self.pos;
+            while self.peek().map_or(false, |c| c != b'\n') { self.bump(); }
+
+            let text = &self.buf[line..self.pos];
+            if text == tag || text.strip_suffix(b"\r") == Some(tag) || self.peek().is_none() {
+                let end = if line > body { line - 1 } else { body };
+                return String::from_utf8_lossy(&self.buf[body..end]).into_owned()
+            }
+
+            self.bump();
+        }
+    }
+}
+
+fn is_space(c: u8) -> bool {
+    match c {
+        b' ' | b'\t' | b'\r' | b'\n' => true,
+        _ => false
+    }
+}
+
+fn is_key_end(c: u8) -> bool {
+    match c {
+        b' ' | b'\t' | b'\r' | b'\n' | b'=' | b':' | b'{' | b'[' | b';' | b',' | b'#' | b'"' | b'\'' => true,
+        _ => false
+    }
+}
+
+fn is_value_end(c: u8) -> bool {
+    match c {
+        b'\n' | b';' | b',' | b'{' | b'}' | b']' | b'#' => true,
+        _ => false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::DEFAULT;
+    use Parser;
+
+    fn source(data: &str) -> Source {
+        Source {
+            file: Some(PathBuf::from("test.conf")),
+            data: data.as_bytes().to_vec(),
+            priority: 0
+        }
+    }
+
+    fn line(table: &Table, obj: &Object) -> Option<usize> {
+        table.get(obj.as_ptr()).map(|loc| loc.line)
+    }
+
+    #[test]
+    fn nested_values() {
+        let s = "a = 1;\nsection {\n  # comment\n  flag = true;\n  list = [\n    1,\n    { b = 2 }\n  ]\n}\n";
+        let obj = Parser::new().parse(s).unwrap();
+        let table = locate(&obj, &source(s), Some(&[]), DEFAULT).unwrap();
+
+        assert_eq!(line(&table, &obj.fetch("a").unwrap()), Some(1));
+        assert_eq!(line(&table, &obj.fetch("section").unwrap()), Some(2));
+        assert_eq!(line(&table, &obj.fetch_path("section.flag").unwrap()), Some(4));
+
+        let list: Vec<_> = obj.fetch_path("section.list").unwrap().iter().collect();
+        assert_eq!(line(&table, &list[0]), Some(6));
+        assert_eq!(line(&table, &list[1]), Some(7));
+        assert_eq!(line(&table, &list[1].fetch("b").unwrap()), Some(7));
+    }
+
+    #[test]
+    fn implicit_arrays() {
+        let s = "a = 1;\n/* multi\nline */\na = 2;\nb \"name\" {\n  c = <<EOD\nx\ny\nEOD\n  d = 3\n}";
+        let obj = Parser::new().parse(s).unwrap();
+        let table = locate(&obj, &source(s), Some(&[]), DEFAULT).unwrap();
+
+        let lines: Vec<_> = obj.fetch("a").unwrap().values().map(|v| line(&table, &v)).collect();
+        assert_eq!(lines, vec![Some(1), Some(4)]);
+        assert_eq!(line(&table, &obj.fetch_path("b.name.c").unwrap()), Some(6));
+        assert_eq!(line(&table, &obj.fetch_path("b.name.d").unwrap()), Some(10));
+    }
+
+    #[test]
+    fn comments_and_strings() {
+        let s = "/* \"*/\" */\na = 1;\nb = <<eod\nc = <<EOD\nx\nEOD\nd = 2;\n";
+        let obj = Parser::new().parse(s).unwrap();
+        let table = locate(&obj, &source(s), Some(&[]), DEFAULT).unwrap();
+
+        assert_eq!(obj.fetch("b").unwrap().as_str(), Some("<<eod"));
+        assert_eq!(line(&table, &obj.fetch("a").unwrap()), Some(2));
+        assert_eq!(line(&table, &obj.fetch("c").unwrap()), Some(4));
+        assert_eq!(line(&table, &obj.fetch("d").unwrap()), Some(7));
+    }
+
+    #[test]
+    fn explicit_arrays() {
+        let s = "a = 1;\nb = 2;\na = 3;";
+        let obj = Parser::with_flags(NO_IMPLICIT_ARRAYS).parse(s).unwrap();
+        let table = locate(&obj, &source(s), Some(&[]), NO_IMPLICIT_ARRAYS).unwrap();
+
+        let lines: Vec<_> = obj.fetch("a").unwrap().iter().map(|v| line(&table, &v)).collect();
+        assert_eq!(lines, vec![Some(1), Some(3)]);
+    }
+
+    #[test]
+    fn lowercase_ascii() {
+        let s = "ÄB = 1;";
+        let obj = Parser::with_flags(LOWERCASE).parse(s).unwrap();
+        let table = locate(&obj, &source(s), Some(&[]), LOWERCASE).unwrap();
+
+        assert_eq!(line(&table, &obj.fetch("Äb").unwrap()), Some(1));
+    }
+
+    #[test]
+    fn unpaired_values() {
+        let obj = Parser::new().parse("a = 1;\na = 2;").unwrap();
+
+        match locate(&obj, &source("a = 1;"), Some(&[]), DEFAULT) {
+            Err(Error::Untracked(_)) => (),
+            res => panic!("unexpected result: {:?}", res.map(|_| ()))
+        }
+    }
+
+    #[test]
+    fn native_includes() {
+        let s = ".try_include \"/nonexistent.conf\"\na = 1;";
+        let obj = Parser::new().parse(s).unwrap();
+
+        match locate(&obj, &source(s), None, DEFAULT) {
+            Err(Error::Untracked(_)) => (),
+            res => panic!("unexpected result: {:?}", res.map(|_| ()))
+        }
+    }
+
+    #[test]
+    fn load_macro() {
+        let entries = scan(&source(".load(key = \"b\", try = true) \"file\"\nc = 1"));
+        let keys: Vec<_> = entries.iter().map(|entry| match *entry {
+            Entry::Value { ref key, ref location, .. } => (key.clone(), location.line),
+            Entry::Include { .. } => (None, 0)
+        }).collect();
+
+        assert_eq!(keys, vec![(Some("b".to_string()), 1), (Some("c".to_string()), 2)]);
+    }
+
+    #[test]
+    fn forgotten_values() {
+        let s = "a { b = 1; }";
+        let obj = Parser::new().parse(s).unwrap();
+        let table = locate(&obj, &source(s), Some(&[]), DEFAULT).unwrap();
+        let a = obj.fetch("a").unwrap();
+
+        table.forget(&a);
+        assert_eq!(line(&table, &a), None);
+        assert_eq!(line(&table, &a.fetch("b").unwrap()), None);
+    }
+
+    #[test]
+    fn display() {
+        let loc = Location { file: Some(PathBuf::from("test.conf")), line: 3 };
+
+        assert_eq!(loc.to_string(), "test.conf:3");
+        assert_eq!(Location { file: None, line: 1 }.to_string(), "<string>:1");
+    }
+}
@@ -1,8 +1,14 @@
 use libucl_sys::*;
+use libc::c_char;
 
-use utils;
+use error::Error;
 use super::Object;
+use Result;
 
+use std::alloc::{
+    handle_alloc_error,
+    Layout
+};
 use std::convert::From;
 
 /// Build element object.
@@ -15,18 +21,87 @@ pub struct Builder {
 
 impl Builder {
     /// Create new `Object` form raw pointer. Internal use only.
-    pub fn from_ptr(obj: *mut ucl_object_t) -> Option<Self> {
+    ///
+    /// Fails with `Error::Alloc` if pointer is null.
+    pub fn from_ptr(obj: *mut ucl_object_t) -> Result<Self> {
         if !obj.is_null() {
-            Some(Builder {
+            Ok(Builder {
                 obj: obj,
             })
         } else {
-            None
+            Err(Error::Alloc)
         }
     }
 
+    /// Unwrap result of libucl constructor, which fails only when allocation fails. Allocation
+    /// failure aborts, same as with `Box::new`.
+    fn alloc(res: Result<Self>) -> Self {
+        match res {
+            Ok(builder) => builder,
+            Err(_) => handle_alloc_error(Layout::new::<ucl_object_t>())
+        }
+    }
+
+    /// Create integer object.
+    ///
+    /// Same as `Builder::from`, but fails with `Error::Alloc` instead of aborting when allocation
+    /// fails.
+    pub fn try_from_int(val: i64) -> Result<Self> {
+        use libc;
+        Builder::from_ptr(unsafe { ucl_object_fromint(val as libc::int64_t) })
+    }
+
+    /// Create float object.
+    ///
+    /// Same as `Builder::from`, but fails with `Error::Alloc` instead of aborting when allocation
+    /// fails.
+    pub fn try_from_float(val: f64) -> Result<Self> {
+        use libc;
+        Builder::from_ptr(unsafe { ucl_object_fromdouble(val as libc::c_double) })
+    }
+
+    /// Create boolean object.
+    ///
+    /// Same as `Builder::from`, but fails with `Error::Alloc` instead of aborting when allocation
+    /// fails.
+    pub fn try_from_bool(val: bool) -> Result<Self> {
+        Builder::from_ptr(unsafe { ucl_object_frombool(val) })
+    }
+
+    /// Create string object.
+    ///
+    /// Same as `Builder::from`, but fails with `Error::Alloc` instead of aborting when allocation
+    /// fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::object::Builder::try_from_str("lol").unwrap().build();
+    /// assert_eq!(obj.as_str(), Some("lol"));
+    /// ```
+    pub fn try_from_str(val: &str) -> Result<Self> {
+        Builder::try_from_bytes(val.as_bytes())
+    }
+
+    /// Create string object holding arbitrary bytes, including NUL and invalid UTF-8.
+    ///
+    /// Same as `Builder::from`, but fails with `Error::Alloc` instead of aborting when allocation
+    /// fails.
+    pub fn try_from_bytes(val: &[u8]) -> Result<Self> {
+        use libc;
+
+        // libucl measures strings of zero length with `strlen`
+        let ptr = if val.is_empty() { b"\0".as_ptr() } else { val.as_ptr() };
+        Builder::from_ptr(unsafe { ucl_object_fromlstring(ptr as *const c_char, val.len() as libc::size_t) })
+    }
+
+    /// Return raw pointer to built object, passing its reference to caller. Internal use only.
+    pub fn into_ptr(self) -> *mut ucl_object_t {
+        self.obj
+    }
+
     pub fn build(self) -> Object {
-        Object::from_cptr(self.obj).unwrap()
+        Object::from_nonnull(self.obj)
     }
 }
 
@@ -37,40 +112,33 @@ impl Into<Object> for Builder {
 }
 
 macro_rules! from_primitive {
-    ($from: ty => $ctype: ident, $func: ident) => {
-        impl From<$from> for Builder {
-            fn from(val: $from) -> Self {
-                use libc;
-                Builder::from_ptr(unsafe { $func(val as libc::$ctype) }).unwrap()
-            }
-        }
-    };
-
     ($from: ty, $func: ident) => {
         impl From<$from> for Builder {
             fn from(val: $from) -> Self {
-                Builder::from_ptr(unsafe { $func(val) }).unwrap()
+                Builder::alloc(Builder::$func(val))
             }
         }
     }
 }
 
-from_primitive!(i64 => int64_t, ucl_object_fromint);
-from_primitive!(f64 => c_double, ucl_object_fromdouble);
-from_primitive!(bool, ucl_object_frombool);
+from_primitive!(i64, try_from_int);
+from_primitive!(f64, try_from_float);
+from_primitive!(bool, try_from_bool);
 
 impl From<String> for Builder {
     fn from(val: String) -> Self {
-        use libc;
-        use libucl_sys::ucl_object_fromlstring;
+        Builder::alloc(Builder::try_from_str(&val))
+    }
+}
 
-        let len = val.len();
-        Builder::from_ptr(unsafe { ucl_object_fromlstring(utils::to_c_str(val), len as libc::size_t) }).unwrap()
+impl<'a> From<&'a [u8]> for Builder {
+    fn from(val: &[u8]) -> Self {
+        Builder::alloc(Builder::try_from_bytes(val))
     }
 }
 
 impl<'a> From<&'a str> for Builder {
     fn from(val: &str) -> Self {
-        From::from(val.to_string())
+        Builder::alloc(Builder::try_from_str(val))
     }
 }
@@ -0,0 +1,61 @@
+use libucl_sys::*;
+
+use super::Object;
+
+/// Comments saved by parser with `SAVE_COMMENTS` flag.
+///
+/// Keeps reference to comments object, so it outlives the parser.
+pub struct Comments {
+    obj: *mut ucl_object_t
+}
+
+impl Comments {
+    /// Create new `Comments` from raw pointer, taking a new reference. Internal use only.
+    pub fn from_cptr(obj: *const ucl_object_t) -> Option<Self> {
+        if obj.is_null() { return None }
+
+        Some(Comments {
+            obj: unsafe { ucl_object_ref(obj) }
+        })
+    }
+
+    /// Return raw pointer to comments object. Internal use only.
+    pub fn as_ptr(&self) -> *const ucl_object_t {
+        self.obj
+    }
+}
+
+impl Drop for Comments {
+    fn drop(&mut self) {
+        unsafe { ucl_object_unref(self.obj) }
+    }
+}
+
+impl Object {
+    /// Return comments preceding object in source document.
+    ///
+    /// Comments are saved only by parser created with `SAVE_COMMENTS` flag. Each comment is
+    /// returned verbatim, including `#` or `/* */` markers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let parser = ucl::Parser::with_flags(ucl::parser::SAVE_COMMENTS);
+    /// let doc = parser.parse("# port to listen on\nport = 80").unwrap();
+    ///
+    /// assert_eq!(doc.fetch("port").unwrap().comments(), vec!["# port to listen on".to_string()]);
+    /// ```
+    pub fn comments(&self) -> Vec<String> {
+        let comments = match self.comments {
+            Some(ref comments) => comments,
+            None => return Vec::new()
+        };
+
+        let found = unsafe { ucl_comments_find(comments.as_ptr(), self.obj) };
+
+        match Object::from_cptr(found) {
+            Some(found) => found.values().filter_map(|comment| comment.as_string()).collect(),
+            None => Vec::new()
+        }
+    }
+}
@@ -1,10 +1,20 @@
 use libucl_sys::*;
+use libc::{
+    self,
+    c_char,
+    c_void
+};
 
+use error::Error;
 use super::Object;
+use Result;
 
-use utils;
+use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::ptr;
+use std::str;
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Emitter {
     JSON,
     JSONCompact,
@@ -13,19 +23,53 @@ pub enum Emitter {
 }
 
 impl Emitter {
-    pub fn emit<T: AsRef<Object>>(&self, obj: T) -> Option<String> {
-        let emit = unsafe { ucl_object_emit(obj.as_ref().obj, Into::into(*self)) };
-        utils::to_str(emit)
+    /// Emit object in given format.
+    ///
+    /// Comments saved by parser are preserved when emitting with `Emitter::Config`. Fails with
+    /// `Error::Emit` if libucl fails or produces output that is not valid UTF-8.
+    pub fn emit<T: AsRef<Object>>(&self, obj: T) -> Result<String> {
+        let obj = obj.as_ref();
+        let comments = match (*self, obj.comments.as_ref()) {
+            (Emitter::Config, Some(comments)) => comments.as_ptr(),
+            _ => ptr::null()
+        };
+
+        unsafe {
+            let mut out: *mut c_void = ptr::null_mut();
+            let funcs = ucl_object_emit_memory_funcs(&mut out);
+            if funcs.is_null() { return Err(Error::Alloc) }
+
+            let ok = ucl_object_emit_full(obj.obj, Into::into(*self), funcs, comments);
+            ucl_object_emit_funcs_free(funcs);
+
+            let res = if !ok {
+                Err(Error::Emit("libucl failed to emit object".to_string()))
+            } else if out.is_null() {
+                Err(Error::Alloc)
+            } else {
+                str::from_utf8(CStr::from_ptr(out as *const c_char).to_bytes())
+                    .map(String::from)
+                    .map_err(|err| Error::Emit(format!("output is not valid UTF-8: {}", err)))
+            };
+            libc::free(out);
+
+            res
+        }
     }
 }
 
-impl From<ucl_emitter> for Emitter {
-    fn from(raw: ucl_emitter) -> Self {
+/// Fails with `Error::Other` for binary emitters, like `UCL_EMIT_MSGPACK`, which have no
+/// counterpart.
+impl TryFrom<ucl_emitter> for Emitter {
+    type Error = Error;
+
+    fn try_from(raw: ucl_emitter) -> Result<Self> {
         match raw {
-            ucl_emitter::UCL_EMIT_JSON         => Emitter::JSON,
-            ucl_emitter::UCL_EMIT_JSON_COMPACT => Emitter::JSONCompact,
-            ucl_emitter::UCL_EMIT_CONFIG       => Emitter::Config,
-            ucl_emitter::UCL_EMIT_YAML         => Emitter::YAML,
+            ucl_emitter::UCL_EMIT_JSON         => Ok(Emitter::JSON),
+            ucl_emitter::UCL_EMIT_JSON_COMPACT => Ok(Emitter::JSONCompact),
+            ucl_emitter::UCL_EMIT_CONFIG       => Ok(Emitter::Config),
+            ucl_emitter::UCL_EMIT_YAML         => Ok(Emitter::YAML),
+            _ => Err(Error::Other)
         }
     }
 }
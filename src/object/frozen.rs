@@ -0,0 +1,230 @@
+//! Immutable trees shareable between threads.
+//!
+//! `Object` is a plain pointer into libucl tree. Children returned by `Object::fetch` or
+//! `Object::iter` point into the same tree and can modify it (i.e. with `Object::merge`), and
+//! libucl reference counting is not atomic, so `Object` is neither `Send` nor `Sync`:
+//!
+//! ```rust,compile_fail
+//! fn assert_send<T: Send>() {}
+//!
+//! assert_send::<ucl::Object>();
+//! ```
+//!
+//! `Frozen` is a read-only handle to a private copy of the tree. The copy is released once, when
+//! the last handle is dropped, and no libucl function that modifies objects is ever called on it,
+//! so handles can be sent to and shared between threads. To modify frozen tree, `Frozen::thaw`
+//! it into a new `Object`, then freeze the result again.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use std::thread;
+//!
+//! let config = ucl::Parser::new().parse("workers = 4").unwrap().freeze().unwrap();
+//! let config = Arc::new(config);
+//!
+//! let handle = {
+//!     let config = config.clone();
+//!     thread::spawn(move || config.fetch("workers").unwrap().as_int())
+//! };
+//!
+//! assert_eq!(handle.join().unwrap(), Some(4));
+//! ```
+
+use libucl_sys::*;
+
+use super::{
+    userdata,
+    Emitter,
+    Object,
+    Type
+};
+use Result;
+
+use std::any::Any;
+use std::fmt;
+use std::str;
+use std::sync::Arc;
+use std::vec;
+
+/// Owner of frozen tree, releases it when dropped.
+struct Tree {
+    root: *mut ucl_object_t
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        unsafe { ucl_object_unref(self.root) }
+    }
+}
+
+/// Read-only handle to value inside frozen tree.
+///
+/// Created by `Object::freeze`. Cloning is cheap, as clones share the same tree.
+#[derive(Clone)]
+pub struct Frozen {
+    tree: Arc<Tree>,
+    obj: *const ucl_object_t
+}
+
+// Tree is never modified after it is frozen, and it is only referenced by `Frozen` handles,
+// which release it exactly once through `Arc`.
+unsafe impl Send for Frozen {}
+unsafe impl Sync for Frozen {}
+
+impl Object {
+    /// Create immutable copy of object, which can be shared between threads.
+    ///
+    /// Source locations and comments are not kept in the copy. Fails with `Error::Alloc` if
+    /// the copy cannot be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::Parser::new().parse("a = 1").unwrap();
+    /// let frozen = obj.freeze().unwrap();
+    ///
+    /// assert_eq!(frozen.fetch("a").unwrap().as_int(), Some(1));
+    /// ```
+    pub fn freeze(&self) -> Result<Frozen> {
+        let root = self.copy()?;
+
+        Ok(Frozen {
+            tree: Arc::new(Tree { root: root }),
+            obj: root
+        })
+    }
+}
+
+impl Frozen {
+    /// Temporary `Object` for reading. It must never escape, as it allows modifications.
+    ///
+    /// It does not take references, as libucl reference counting is not atomic.
+    fn object(&self) -> Object {
+        Object::view(self.obj)
+    }
+
+    fn wrap(&self, obj: Object) -> Frozen {
+        Frozen {
+            tree: self.tree.clone(),
+            obj: obj.obj
+        }
+    }
+
+    fn wrap_all<I: Iterator<Item = Object>>(&self, objs: I) -> vec::IntoIter<Frozen> {
+        objs.map(|obj| self.wrap(obj)).collect::<Vec<_>>().into_iter()
+    }
+
+    /// Return raw pointer to underlying libucl object.
+    pub fn as_ptr(&self) -> *const ucl_object_t {
+        self.obj
+    }
+
+    /// Create mutable deep copy of value.
+    pub fn thaw(&self) -> Result<Object> {
+        self.object().deep_clone()
+    }
+
+    /// Return type of value. See `Object::get_type`.
+    pub fn get_type(&self) -> Type {
+        self.object().get_type()
+    }
+
+    /// Return key assigned to value. See `Object::key`.
+    pub fn key(&self) -> Option<String> {
+        self.object().key()
+    }
+
+    /// Return priority assigned to value. See `Object::priority`.
+    pub fn priority(&self) -> usize {
+        self.object().priority()
+    }
+
+    /// Return `i64` value. See `Object::as_int`.
+    pub fn as_int(&self) -> Option<i64> {
+        self.object().as_int()
+    }
+
+    /// Return `f64` value. See `Object::as_float`.
+    pub fn as_float(&self) -> Option<f64> {
+        self.object().as_float()
+    }
+
+    /// Return boolean value. See `Object::as_bool`.
+    pub fn as_bool(&self) -> Option<bool> {
+        self.object().as_bool()
+    }
+
+    /// Return string value. See `Object::as_string`.
+    pub fn as_string(&self) -> Option<String> {
+        self.object().as_string()
+    }
+
+    /// Return string value without copying. See `Object::as_str`.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes().and_then(|bytes| str::from_utf8(bytes).ok())
+    }
+
+    /// Return raw bytes of string value. See `Object::as_bytes`.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        // tree outlives `self`
+        unsafe { super::string_bytes(self, self.obj) }
+    }
+
+    /// Fetch value under key. See `Object::fetch`.
+    pub fn fetch<T: AsRef<str>>(&self, key: T) -> Option<Frozen> {
+        self.object().fetch(key).map(|obj| self.wrap(obj))
+    }
+
+    /// Fetch value at the end of path delimited by `.` (dot). See `Object::fetch_path`.
+    pub fn fetch_path<T: AsRef<str>>(&self, path: T) -> Option<Frozen> {
+        self.object().fetch_path(path).map(|obj| self.wrap(obj))
+    }
+
+    /// Look up value by JSON Pointer. See `Object::pointer`.
+    pub fn pointer(&self, pointer: &str) -> Option<Frozen> {
+        self.object().pointer(pointer).map(|obj| self.wrap(obj))
+    }
+
+    /// Return values matching given selector. See `Object::query`.
+    pub fn query<T: AsRef<str>>(&self, selector: T) -> Result<vec::IntoIter<Frozen>> {
+        self.object().query(selector).map(|matches| self.wrap_all(matches))
+    }
+
+    /// Iterate over elements of object or array. See `Object::iter`.
+    pub fn iter(&self) -> vec::IntoIter<Frozen> {
+        self.wrap_all(self.object().iter())
+    }
+
+    /// Iterate over all values of implicit array. See `Object::values`.
+    pub fn values(&self) -> vec::IntoIter<Frozen> {
+        self.wrap_all(self.object().values())
+    }
+
+    /// Return reference to value held by user data object. See `Object::downcast_ref`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        // tree outlives `self`
+        unsafe { userdata::downcast_ref(self.obj) }
+    }
+
+    /// Check if value is an implicit array. See `Object::is_implicit_array`.
+    pub fn is_implicit_array(&self) -> bool {
+        self.object().is_implicit_array()
+    }
+
+    /// Emit value in given format. See `Emitter::emit`.
+    pub fn emit(&self, emitter: Emitter) -> Result<String> {
+        emitter.emit(self.object())
+    }
+}
+
+impl fmt::Debug for Frozen {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.object(), fmt)
+    }
+}
+
+impl PartialEq for Frozen {
+    fn eq(&self, other: &Self) -> bool {
+        self.object() == other.object()
+    }
+}
@@ -0,0 +1,56 @@
+use libucl_sys::*;
+
+use super::{
+    Object,
+    Type
+};
+
+use std::ptr;
+use std::vec;
+
+/// Iterator over `Object` elements.
+///
+/// Created by `Object::iter` and `Object::values`. Elements are collected when iterator is
+/// created, so modifying the tree (i.e. with `Object::merge`) does not affect iterators that
+/// already exist.
+pub struct Iter {
+    inner: vec::IntoIter<Object>
+}
+
+impl Iter {
+    /// Create new iterator over elements of `obj` (when `expand` is set) or over values of
+    /// implicit array. Internal use only.
+    pub fn new(obj: &Object, expand: bool) -> Self {
+        let container = match obj.get_type() {
+            Type::Object | Type::Array => true,
+            _ => false
+        };
+
+        let mut elements = Vec::new();
+        let mut iter: ucl_object_iter_t = ptr::null_mut();
+
+        // libucl frees its internal hash iterator only when iteration reaches the end, so it
+        // must not be interrupted
+        if container || !expand {
+            loop {
+                let out = unsafe { ucl_iterate_object(obj.obj, &mut iter, expand) };
+                match if expand { obj.child(out) } else { obj.element(out) } {
+                    Some(elt) => elements.push(elt),
+                    None => break
+                }
+            }
+        }
+
+        Iter {
+            inner: elements.into_iter()
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = Object;
+
+    fn next(&mut self) -> Option<Object> {
+        self.inner.next()
+    }
+}
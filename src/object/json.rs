@@ -0,0 +1,216 @@
+//! Conversion between `Object` and `serde_json::Value`.
+//!
+//! Mapping of UCL types that have no JSON counterpart:
+//!
+//! - `Type::Time` is converted to number of seconds as float
+//! - `Type::UserData` is converted to string returned by its emitter or to `null` if there is
+//!   no emitter
+//! - implicit arrays are converted to explicit JSON arrays
+
+use libucl_sys::*;
+use libc::{
+    c_char,
+    size_t
+};
+use serde_json::{
+    Map,
+    Number,
+    Value
+};
+
+use error::Error;
+use super::{
+    Builder,
+    Object,
+    Type
+};
+use utils;
+
+use std::convert::TryFrom;
+
+impl<'a> From<&'a Object> for Value {
+    /// Convert `Object` to `serde_json::Value`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate ucl;
+    /// # extern crate serde_json;
+    /// # fn main() {
+    /// let obj = ucl::Parser::new().parse("a = 1; a = 2; b = 10min").unwrap();
+    /// let value = serde_json::Value::from(&obj);
+    ///
+    /// assert_eq!(value["a"], serde_json::json!([1, 2]));
+    /// assert_eq!(value["b"], serde_json::json!(600.0));
+    /// # }
+    /// ```
+    fn from(obj: &Object) -> Self {
+        let mut values: Vec<Value> = obj.values().map(|val| single(&val)).collect();
+
+        match values.len() {
+            1 => values.remove(0),
+            _ => Value::Array(values)
+        }
+    }
+}
+
+impl From<Object> for Value {
+    fn from(obj: Object) -> Self {
+        From::from(&obj)
+    }
+}
+
+fn single(obj: &Object) -> Value {
+    match obj.get_type() {
+        Type::Object => {
+            let mut map = Map::new();
+            for val in obj.iter() {
+                if let Some(key) = val.key() {
+                    map.insert(key, Value::from(&val));
+                }
+            }
+            Value::Object(map)
+        },
+        Type::Array => Value::Array(obj.iter().map(|val| Value::from(&val)).collect()),
+        Type::Int => obj.as_int().map(Value::from).unwrap_or(Value::Null),
+        Type::Float | Type::Time => {
+            let secs = unsafe { ucl_object_todouble(obj.obj) };
+            Number::from_f64(secs).map(Value::Number).unwrap_or(Value::Null)
+        },
+        Type::String => obj.as_string().map(Value::String).unwrap_or(Value::Null),
+        Type::Boolean => obj.as_bool().map(Value::Bool).unwrap_or(Value::Null),
+        Type::UserData => {
+            let ud = obj.obj as *const ucl_object_userdata;
+            let out = unsafe {
+                match (*ud).emitter {
+                    Some(emitter) => utils::to_str(emitter((*ud).obj.uv())),
+                    None => None
+                }
+            };
+            out.map(Value::String).unwrap_or(Value::Null)
+        },
+        Type::Null => Value::Null
+    }
+}
+
+impl TryFrom<Value> for Builder {
+    type Error = Error;
+
+    /// Convert `serde_json::Value` to `Builder`.
+    ///
+    /// Fails with `Error::Conversion` when number cannot be represented by UCL without loss of
+    /// precision, or `Error::Alloc` when libucl cannot allocate values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # extern crate ucl;
+    /// # extern crate serde_json;
+    /// # use std::convert::TryFrom;
+    /// # fn main() {
+    /// let value = serde_json::json!({ "a": { "b": [1, 2] } });
+    /// let obj = ucl::object::Builder::try_from(value).unwrap().build();
+    ///
+    /// assert_eq!(obj.fetch_path("a.b").unwrap().iter().count(), 2);
+    /// # }
+    /// ```
+    fn try_from(value: Value) -> Result<Self, Error> {
+        to_ptr(&value).and_then(Builder::from_ptr)
+    }
+}
+
+fn to_ptr(value: &Value) -> Result<*mut ucl_object_t, Error> {
+    let ptr = match *value {
+        Value::Null => unsafe { ucl_object_typed_new(ucl_type_t::UCL_NULL) },
+        Value::Bool(val) => unsafe { ucl_object_frombool(val) },
+        Value::Number(ref num) => {
+            match (num.as_i64(), num.as_f64()) {
+                (Some(val), _) => unsafe { ucl_object_fromint(val) },
+                (None, Some(val)) if num.is_f64() => unsafe { ucl_object_fromdouble(val) },
+                _ => return Err(Error::Conversion(format!("number {} is out of range", num)))
+            }
+        },
+        Value::String(ref val) => return Builder::try_from_str(val).map(Builder::into_ptr),
+        Value::Array(ref vals) => {
+            let top = unsafe { ucl_object_typed_new(ucl_type_t::UCL_ARRAY) };
+            if top.is_null() { return Err(Error::Alloc) }
+            for val in vals {
+                let elt = to_ptr(val).map_err(|err| { unsafe { ucl_object_unref(top) }; err })?;
+                if !unsafe { ucl_array_append(top, elt) } {
+                    unsafe {
+                        ucl_object_unref(elt);
+                        ucl_object_unref(top);
+                    }
+                    return Err(Error::Alloc)
+                }
+            }
+            top
+        },
+        Value::Object(ref map) => {
+            let top = unsafe { ucl_object_typed_new(ucl_type_t::UCL_OBJECT) };
+            if top.is_null() { return Err(Error::Alloc) }
+            for (key, val) in map {
+                let elt = to_ptr(val).map_err(|err| { unsafe { ucl_object_unref(top) }; err })?;
+                let key_ptr = key.as_ptr() as *const c_char;
+                if !unsafe { ucl_object_insert_key(top, elt, key_ptr, key.len() as size_t, true) } {
+                    unsafe {
+                        ucl_object_unref(elt);
+                        ucl_object_unref(top);
+                    }
+                    return Err(Error::Alloc)
+                }
+            }
+            top
+        }
+    };
+
+    if ptr.is_null() { Err(Error::Alloc) } else { Ok(ptr) }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{
+        json,
+        Value
+    };
+
+    use object::Builder;
+    use Parser;
+
+    use std::convert::TryFrom;
+
+    #[test]
+    fn implicit_array() {
+        let obj = Parser::new().parse("a = 1; a = 2;").unwrap();
+        assert_eq!(Value::from(&obj), json!({ "a": [1, 2] }));
+    }
+
+    #[test]
+    fn time_as_seconds() {
+        let obj = Parser::new().parse("a = 1.5s;").unwrap();
+        assert_eq!(Value::from(&obj), json!({ "a": 1.5 }));
+    }
+
+    #[test]
+    fn round_trip() {
+        let value = json!({
+            "param": "value",
+            "empty": "",
+            "section": {
+                "flag": true,
+                "number": 10000,
+                "ratio": 0.5,
+                "none": null,
+                "hosts": [{ "host": "localhost", "port": 9000 }]
+            }
+        });
+        let obj = Builder::try_from(value.clone()).unwrap().build();
+
+        assert_eq!(Value::from(&obj), value);
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert!(Builder::try_from(json!(u64::max_value())).is_err());
+    }
+}
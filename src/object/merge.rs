@@ -0,0 +1,135 @@
+use libucl_sys::*;
+use libc::{
+    c_char,
+    size_t
+};
+
+use error::Error;
+use location::Table;
+use super::{
+    Object,
+    Type
+};
+use Result;
+
+bitflags! {
+    flags Strategy: u32 {
+        const REPLACE       = 0x0,
+        const APPEND_ARRAYS = 0x1,
+        const DEEP_MERGE    = 0x2,
+        const PRIORITY      = 0x4
+    }
+}
+
+impl Object {
+    /// Merge keys of `other` object into this object.
+    ///
+    /// Keys that are missing in this object are always copied. Behaviour for existing keys
+    /// depends on given strategy flags:
+    ///
+    /// - `REPLACE` - replace existing value with value from `other`
+    /// - `APPEND_ARRAYS` - append elements of arrays to existing arrays
+    /// - `DEEP_MERGE` - merge nested objects recursively
+    /// - `PRIORITY` - keep existing value if it has higher priority, replace it if it has lower
+    ///   priority and apply remaining flags only when priorities are equal
+    ///
+    /// Values from `other` are copied, so `other` stays untouched. User data values are shared
+    /// between both trees. Both objects must be of
+    /// `Type::Object`.
+    ///
+    /// Fails with `Error::Alloc` if value cannot be copied, or `Error::Internal` if libucl refuses
+    /// to insert it. Keys merged before the failure stay in this object.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ucl::object::merge;
+    ///
+    /// let mut defaults = ucl::Parser::new().parse("a { b = 1; c = 2; }").unwrap();
+    /// let overrides = ucl::Parser::new().parse("a { c = 3; }").unwrap();
+    ///
+    /// defaults.merge(&overrides, merge::DEEP_MERGE).unwrap();
+    ///
+    /// assert_eq!(defaults.fetch_path("a.b").unwrap().as_int(), Some(1));
+    /// assert_eq!(defaults.fetch_path("a.c").unwrap().as_int(), Some(3));
+    /// ```
+    pub fn merge(&mut self, other: &Object, strategy: Strategy) -> Result<()> {
+        if self.get_type() != Type::Object || other.get_type() != Type::Object {
+            return Err(Error::TypeMismatch(format!("cannot merge {:?} into {:?}",
+                                                   other.get_type(),
+                                                   self.get_type())))
+        }
+
+        let locations = self.locations.as_ref().map(|locations| &**locations);
+
+        unsafe { merge_object(self.obj as *mut ucl_object_t, other, strategy, locations) }
+    }
+}
+
+unsafe fn merge_object(top: *mut ucl_object_t, other: &Object, strategy: Strategy, locations: Option<&Table>) -> Result<()> {
+    for val in other.iter() {
+        let mut len = 0;
+        let key = ucl_object_keyl(val.obj, &mut len);
+        let found = ucl_object_find_keyl(top, key, len) as *mut ucl_object_t;
+
+        if found.is_null() {
+            let elt = val.copy()?;
+            if !ucl_object_insert_key(top, elt, key, len, true) {
+                ucl_object_unref(elt);
+                return Err(Error::Internal)
+            }
+            continue
+        }
+
+        if strategy.contains(PRIORITY) {
+            let old = ucl_object_get_priority(found);
+            let new = ucl_object_get_priority(val.obj);
+
+            if old > new { continue }
+            if old < new {
+                replace(top, found, &val, key, len, locations)?;
+                continue
+            }
+        }
+
+        match (Type::from(ucl_object_type(found)), val.get_type()) {
+            (Type::Object, Type::Object) if strategy.contains(DEEP_MERGE) => {
+                merge_object(found, &val, strategy, locations)?
+            },
+            (Type::Array, Type::Array) if strategy.contains(APPEND_ARRAYS) => {
+                for elt in val.iter() {
+                    let elt = elt.copy()?;
+                    if !ucl_array_append(found, elt) {
+                        ucl_object_unref(elt);
+                        return Err(Error::Internal)
+                    }
+                }
+            },
+            _ => replace(top, found, &val, key, len, locations)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace existing value `found` under given key with copy of `val`.
+unsafe fn replace(top: *mut ucl_object_t,
+                  found: *const ucl_object_t,
+                  val: &Object,
+                  key: *const c_char,
+                  len: size_t,
+                  locations: Option<&Table>) -> Result<()> {
+    let elt = val.copy()?;
+
+    if let Some(locations) = locations {
+        locations.forget(&Object::view(found));
+    }
+
+    // replaced value is released by libucl, objects pointing to it hold their own references
+    if !ucl_object_replace_key(top, elt, key, len, true) {
+        ucl_object_unref(elt);
+        return Err(Error::Internal)
+    }
+
+    Ok(())
+}
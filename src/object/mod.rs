@@ -1,16 +1,49 @@
 use libucl_sys::*;
+use libc::c_char;
 
 pub use self::types::Type;
 pub use self::builder::Builder;
 pub use self::emitter::Emitter;
+pub use self::comments::Comments;
+pub use self::iter::Iter;
+pub use self::merge::Strategy;
+pub use self::query::Selector;
+pub use self::pointer::ObjectMut;
+pub use self::frozen::Frozen;
+use location::{
+    Location,
+    Table
+};
+use error::Error;
 use utils;
+use Result;
 
+use std::cmp::Ordering;
 use std::convert::From;
 use std::fmt;
+use std::hash::{
+    Hash,
+    Hasher
+};
+use std::ptr;
+use std::rc::Rc;
+use std::slice;
+use std::str;
+use std::sync::Arc;
 
 pub mod types;
 pub mod builder;
 pub mod emitter;
+pub mod iter;
+pub mod comments;
+pub mod merge;
+pub mod query;
+pub mod pointer;
+pub mod frozen;
+pub mod userdata;
+
+#[cfg(feature = "serde_json")]
+mod json;
 
 #[cfg(test)]
 mod test;
@@ -19,33 +52,154 @@ mod test;
 ///
 /// This structure is immutable typed reference to object inside parsed tree. It can be one of
 /// `Type` elements and can be cast only to given type.
+///
+/// Every `Object` holds a reference to its value, so values removed from the tree (i.e. by
+/// `ObjectMut::set` or `Object::merge`) stay valid as long as there are objects pointing to
+/// them.
+///
+/// `Object` is neither `Send` nor `Sync`, use `Object::freeze` to share tree between threads.
 pub struct Object {
     obj: *const ucl_object_t,
-    typ: Type
+    /// Value keeping `obj` alive, which is the first value of implicit array for values taken
+    /// out of it. Null for objects that do not hold a reference.
+    owner: *mut ucl_object_t,
+    typ: Type,
+    locations: Option<Rc<Table>>,
+    comments: Option<Arc<Comments>>
 }
 
 impl Object {
-    /// Create new `Object` form const raw pointer. Internal use only.
-    fn from_cptr(obj: *const ucl_object_t) -> Option<Self> {
+    /// Create new `Object` form const raw pointer, taking a new reference. Internal use only.
+    pub fn from_cptr(obj: *const ucl_object_t) -> Option<Self> {
         if !obj.is_null() {
-            Some(Object {
-                obj: obj,
-                typ: Type::from(unsafe { ucl_object_type(obj) })
-            })
+            Some(Object::from_nonnull(unsafe { ucl_object_ref(obj) }))
         } else {
             None
         }
     }
 
-    // pub fn priority(&self) -> usize {
-    //     unsafe { ucl_object_get_priority(self.obj) as usize }
-    // }
+    /// Create new `Object` from pointer known not to be null, taking over reference owned by
+    /// caller.
+    fn from_nonnull(obj: *const ucl_object_t) -> Self {
+        let mut res = Object::view(obj);
+        res.owner = obj as *mut _;
+
+        res
+    }
+
+    /// Create new `Object` without taking a reference, for trees that are kept alive
+    /// otherwise. Children of such object do not take references either.
+    fn view(obj: *const ucl_object_t) -> Self {
+        Object {
+            obj: obj,
+            owner: ptr::null_mut(),
+            typ: Type::from(unsafe { ucl_object_type(obj) }),
+            locations: None,
+            comments: None
+        }
+    }
+
+    /// Return raw pointer to underlying libucl object.
+    pub fn as_ptr(&self) -> *const ucl_object_t {
+        self.obj
+    }
+
+    /// Attach source locations table to object. Internal use only.
+    pub fn with_locations(mut self, locations: Option<Rc<Table>>) -> Self {
+        self.locations = locations;
+        self
+    }
+
+    /// Attach comments saved by parser to object. Internal use only.
+    pub fn with_comments(mut self, comments: Option<Arc<Comments>>) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    /// Create `Object` for child of this object, sharing source locations and comments.
+    fn child(&self, obj: *const ucl_object_t) -> Option<Object> {
+        if obj.is_null() { return None }
+
+        let owner = if self.owner.is_null() { ptr::null_mut() } else { obj as *mut _ };
+        Some(self.related(obj, owner))
+    }
+
+    /// Create `Object` for value of implicit array this object belongs to. It is kept alive by
+    /// the same value as this object, as values are released together with the first one.
+    fn element(&self, obj: *const ucl_object_t) -> Option<Object> {
+        if obj.is_null() { return None }
+
+        Some(self.related(obj, self.owner))
+    }
+
+    /// Create another handle to the same object.
+    fn share(&self) -> Object {
+        self.related(self.obj, self.owner)
+    }
+
+    fn related(&self, obj: *const ucl_object_t, owner: *mut ucl_object_t) -> Object {
+        if !owner.is_null() { unsafe { ucl_object_ref(owner) }; }
+
+        Object {
+            obj: obj,
+            owner: owner,
+            typ: Type::from(unsafe { ucl_object_type(obj) }),
+            locations: self.locations.clone(),
+            comments: self.comments.clone()
+        }
+    }
+
+    /// Return location of object in source document.
+    ///
+    /// Locations are available only for objects parsed by `Parser` with enabled location
+    /// tracking, see `Parser::track_locations`. Values removed from the tree (i.e. by
+    /// `ObjectMut::set`) lose their locations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut parser = ucl::Parser::new();
+    /// parser.track_locations();
+    ///
+    /// let doc = parser.parse("a = 1;\nb {\n  c = 2;\n}").unwrap();
+    /// let loc = doc.fetch_path("b.c").unwrap().source_location().unwrap();
+    ///
+    /// assert_eq!(loc.line, 3);
+    /// assert_eq!(loc.to_string(), "<string>:3");
+    /// ```
+    pub fn source_location(&self) -> Option<Location> {
+        self.locations.as_ref().and_then(|locations| locations.get(self.obj))
+    }
+
+    /// Return priority assigned to object.
+    pub fn priority(&self) -> usize {
+        unsafe { ucl_object_get_priority(self.obj) as usize }
+    }
 
     /// Return key assigned to object.
     pub fn key(&self) -> Option<String> {
         utils::to_str(unsafe { ucl_object_key(self.obj) })
     }
 
+    /// Return raw bytes of key assigned to object
+    ///
+    /// Unlike `Object::key`, keys that are not valid UTF-8 are returned too.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::Parser::new().parse("a = 1").unwrap();
+    ///
+    /// assert_eq!(obj.fetch("a").unwrap().key_bytes(), Some(&b"a"[..]));
+    /// assert_eq!(obj.key_bytes(), None);
+    /// ```
+    pub fn key_bytes(&self) -> Option<&[u8]> {
+        let mut len = 0;
+        let key = unsafe { ucl_object_keyl(self.obj, &mut len) };
+
+        if key.is_null() { None } else { Some(unsafe { slice::from_raw_parts(key as *const u8, len) }) }
+    }
+
     /// Return type of object.
     pub fn get_type(&self) -> Type {
         self.typ
@@ -147,15 +301,41 @@ impl Object {
     /// assert_eq!(obj.as_string(), None);
     /// ```
     pub fn as_string(&self) -> Option<String> {
-        use libucl_sys::ucl_object_tostring;
-
-        if self.get_type() != Type::String { return None }
+        self.as_str().map(String::from)
+    }
 
-        unsafe {
-            let out = ucl_object_tostring(self.obj);
+    /// Return string value without copying
+    ///
+    /// Returns `None` if string is not valid UTF-8, use `Object::as_bytes` to read such values.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::object::Builder::from("lol").build();
+    /// assert_eq!(obj.as_str(), Some("lol"));
+    ///
+    /// let obj = ucl::object::Builder::from(&b"\xe9t\xe9"[..]).build();
+    /// assert_eq!(obj.as_str(), None);
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes().and_then(|bytes| str::from_utf8(bytes).ok())
+    }
 
-            utils::to_str(out)
-        }
+    /// Return raw bytes of string value
+    ///
+    /// Strings can contain any bytes, including NUL.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::object::Builder::from(&b"a\0b"[..]).build();
+    /// assert_eq!(obj.as_bytes(), Some(&b"a\0b"[..]));
+    ///
+    /// let obj = ucl::object::Builder::from(10).build();
+    /// assert_eq!(obj.as_bytes(), None);
+    /// ```
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        unsafe { string_bytes(self, self.obj) }
     }
 
     /// Fetch object under key
@@ -167,14 +347,16 @@ impl Object {
     /// assert_eq!(obj.fetch("a").unwrap().as_string(), Some("b".to_string()));
     /// ```
     pub fn fetch<T: AsRef<str>>(&self, key: T) -> Option<Object> {
-        use libucl_sys::ucl_object_find_key;
+        use libucl_sys::ucl_object_find_keyl;
 
         if self.get_type() != Type::Object { return None }
 
+        let key = key.as_ref();
+
         unsafe {
-            let out = ucl_object_find_key(self.obj, utils::to_c_str(key.as_ref()));
+            let out = ucl_object_find_keyl(self.obj, key.as_ptr() as *const c_char, key.len());
 
-            Object::from_cptr(out)
+            self.child(out)
         }
     }
 
@@ -191,10 +373,92 @@ impl Object {
 
         if self.get_type() != Type::Object { return None }
 
+        // paths with NUL bytes cannot exist in the tree
+        let path = utils::to_c_str(path.as_ref()).ok()?;
+
         unsafe {
-            let out = ucl_lookup_path(self.obj, utils::to_c_str(path.as_ref()));
+            let out = ucl_lookup_path(self.obj, path.as_ptr());
 
-            Object::from_cptr(out)
+            self.child(out)
+        }
+    }
+
+    /// Iterate over elements of object or array.
+    ///
+    /// For objects it yields values, use `Object::key` to get their keys. Values with
+    /// non-unique keys are returned as single implicit array, see `Object::values`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::Parser::new().parse("a = 1; b = 2;").unwrap();
+    /// assert_eq!(obj.iter().count(), 2);
+    /// ```
+    pub fn iter(&self) -> Iter {
+        Iter::new(self, true)
+    }
+
+    /// Iterate over all values of implicit array.
+    ///
+    /// Non-unique keys in objects are converted to implicit arrays. This yields every value
+    /// stored under the key of the object. For ordinary values it yields only the value itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::Parser::new().parse("a = 1; a = 2;").unwrap();
+    /// let a = obj.fetch("a").unwrap();
+    ///
+    /// assert_eq!(a.as_int(), Some(1));
+    /// assert_eq!(a.values().map(|v| v.as_int().unwrap()).collect::<Vec<_>>(), vec![1, 2]);
+    /// ```
+    pub fn values(&self) -> Iter {
+        Iter::new(self, false)
+    }
+
+    /// Check if object is an implicit array, i.e. holds more than one value.
+    pub fn is_implicit_array(&self) -> bool {
+        self.values().nth(1).is_some()
+    }
+
+    /// Create deep copy of object.
+    ///
+    /// Returned object is independent from original tree, so it can be modified without
+    /// affecting the original.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ucl::object::merge;
+    ///
+    /// let doc = ucl::Parser::new().parse("defaults { port = 80; }").unwrap();
+    /// let overrides = ucl::Parser::new().parse("port = 8080").unwrap();
+    ///
+    /// let mut tenant = doc.fetch("defaults").unwrap().deep_clone().unwrap();
+    /// tenant.merge(&overrides, merge::REPLACE).unwrap();
+    ///
+    /// assert_eq!(tenant.fetch("port").unwrap().as_int(), Some(8080));
+    /// assert_eq!(doc.fetch_path("defaults.port").unwrap().as_int(), Some(80));
+    /// ```
+    pub fn deep_clone(&self) -> Result<Object> {
+        self.copy().map(|obj| Object::from_nonnull(obj))
+    }
+
+    /// Create deep copy of object, sharing values of user data objects.
+    fn copy(&self) -> Result<*mut ucl_object_t> {
+        let copy = unsafe { ucl_object_copy(self.obj) };
+        if copy.is_null() { return Err(Error::Alloc) }
+
+        userdata::retain_copied(&Object::view(copy));
+
+        Ok(copy)
+    }
+}
+
+impl Drop for Object {
+    fn drop(&mut self) {
+        if !self.owner.is_null() {
+            unsafe { ucl_object_unref(self.owner) }
         }
     }
 }
@@ -204,13 +468,136 @@ impl AsRef<Object> for Object {
 }
 
 impl fmt::Debug for Object {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let string = Emitter::JSON.emit(&self);
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match Emitter::JSON.emit(self) {
+            Ok(string) => fmt.write_str(&string),
+            Err(_) => Err(fmt::Error)
+        }
+    }
+}
 
-        if string.is_some() {
-            fmt.write_str(&string.unwrap())
-        } else {
-            Err(fmt::Error)
+/// Return bytes of string object, borrowed for lifetime of `_owner` which must keep the tree
+/// containing object alive.
+unsafe fn string_bytes<'a, T>(_owner: &'a T, obj: *const ucl_object_t) -> Option<&'a [u8]> {
+    let mut out = ptr::null();
+    let mut len = 0;
+
+    if Type::from(ucl_object_type(obj)) != Type::String { return None }
+    if !ucl_object_tolstring_safe(obj, &mut out, &mut len) { return None }
+
+    if out.is_null() {
+        Some(&[])
+    } else {
+        Some(slice::from_raw_parts(out as *const u8, len as usize))
+    }
+}
+
+/// Objects are compared structurally.
+///
+/// Objects of different types are never equal and are ordered by `Type`. Numbers and strings
+/// are compared by value, objects by entries sorted by key, and arrays and implicit arrays
+/// element-wise. User data objects are equal only to copies of themselves.
+///
+/// This deliberately differs from libucl's `ucl_object_compare`, which is not a total order: it
+/// truncates difference of numbers to integer (so `1.25` equals `1.5`), orders strings and arrays
+/// by length first, compares strings only up to first NUL byte and reports objects with
+/// different keys as greater than each other.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Object {}
+
+impl PartialOrd for Object {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Object {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_by(self.values(), other.values(), |a, b| a.cmp_value(b))
+    }
+}
+
+/// Structural hash consistent with `PartialEq`.
+impl Hash for Object {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.values().count().hash(state);
+        for val in self.values() { val.hash_value(state) }
+    }
+}
+
+impl Object {
+    /// Return entries of object sorted by key.
+    fn sorted_entries(&self) -> Vec<Object> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|a, b| a.key_bytes().cmp(&b.key_bytes()));
+        entries
+    }
+
+    /// Compare single value, ignoring other values of implicit array.
+    fn cmp_value(&self, other: &Object) -> Ordering {
+        let typ = self.get_type();
+        if typ != other.get_type() { return (typ as u8).cmp(&(other.get_type() as u8)) }
+
+        match typ {
+            Type::Object => cmp_by(self.sorted_entries().into_iter(),
+                                   other.sorted_entries().into_iter(),
+                                   |a, b| a.key_bytes().cmp(&b.key_bytes()).then_with(|| a.cmp(b))),
+            Type::Array => cmp_by(self.iter(), other.iter(), |a, b| a.cmp(b)),
+            Type::Int => self.as_int().cmp(&other.as_int()),
+            Type::Float | Type::Time => unsafe {
+                ucl_object_todouble(self.obj).total_cmp(&ucl_object_todouble(other.obj))
+            },
+            Type::String => self.as_bytes().cmp(&other.as_bytes()),
+            Type::Boolean => self.as_bool().cmp(&other.as_bool()),
+            // copies of user data object share the payload
+            Type::UserData => unsafe { ((*self.obj).uv() as usize).cmp(&((*other.obj).uv() as usize)) },
+            Type::Null => Ordering::Equal
+        }
+    }
+
+    /// Hash single value, ignoring other values of implicit array.
+    fn hash_value<H: Hasher>(&self, state: &mut H) {
+        (self.get_type() as u8).hash(state);
+
+        match self.get_type() {
+            Type::Object => {
+                let entries = self.sorted_entries();
+                entries.len().hash(state);
+                for val in entries {
+                    val.key_bytes().hash(state);
+                    val.hash(state);
+                }
+            },
+            Type::Array => {
+                self.iter().count().hash(state);
+                for val in self.iter() { val.hash(state) }
+            },
+            Type::Int => self.as_int().hash(state),
+            Type::Float | Type::Time => unsafe { ucl_object_todouble(self.obj).to_bits().hash(state) },
+            Type::String => self.as_bytes().hash(state),
+            Type::Boolean => self.as_bool().hash(state),
+            Type::UserData => unsafe { ((*self.obj).uv() as usize).hash(state) },
+            Type::Null => ()
+        }
+    }
+}
+
+/// Compare sequences lexicographically using given comparator.
+fn cmp_by<I, F>(mut lhs: I, mut rhs: I, cmp: F) -> Ordering
+    where I: Iterator, F: Fn(&I::Item, &I::Item) -> Ordering
+{
+    loop {
+        match (lhs.next(), rhs.next()) {
+            (Some(a), Some(b)) => match cmp(&a, &b) {
+                Ordering::Equal => (),
+                res => return res
+            },
+            (a, b) => return a.is_some().cmp(&b.is_some())
         }
     }
 }
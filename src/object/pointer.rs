@@ -0,0 +1,212 @@
+//! JSON Pointer (RFC 6901) lookup.
+//!
+//! Pointer is a list of reference tokens prefixed with `/`, where `~1` stands for `/` and `~0`
+//! for `~`. Empty pointer refers to whole document. Values stored under non-unique keys (implicit
+//! arrays) are addressed by index, just like elements of arrays.
+
+use libc::{
+    c_char,
+    c_uint
+};
+use libucl_sys::*;
+
+use error::Error;
+use super::{
+    Builder,
+    Object,
+    Type
+};
+use Result;
+
+use std::convert::TryInto;
+use std::ops::{
+    Deref,
+    DerefMut
+};
+
+/// Location of referenced value, with its parent object or array.
+enum Slot {
+    Root,
+    Key(Object, String),
+    Index(Object, usize)
+}
+
+/// Mutable reference to value found by `Object::pointer_mut`.
+///
+/// Dereferences to found `Object`, so in-place modifications (i.e. `Object::merge`) are visible
+/// in the tree. Use `ObjectMut::set` to replace value.
+pub struct ObjectMut<'a> {
+    root: &'a mut Object,
+    slot: Slot,
+    value: Object
+}
+
+impl<'a> ObjectMut<'a> {
+    /// Replace referenced value with new one.
+    ///
+    /// Objects pointing to replaced value stay valid, but they are no longer part of the tree.
+    ///
+    /// Fails with `Error::Alloc` if new value cannot be built, or `Error::Internal` if libucl
+    /// refuses to replace the value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut obj = ucl::Parser::new().parse("a { b = [1, 2]; }").unwrap();
+    ///
+    /// obj.pointer_mut("/a/b/1").unwrap().set(20).unwrap();
+    ///
+    /// assert_eq!(obj.pointer("/a/b/1").unwrap().as_int(), Some(20));
+    /// ```
+    pub fn set<T>(self, value: T) -> Result<()>
+        where T: TryInto<Builder>, Error: From<T::Error>
+    {
+        let value = value.try_into()?;
+
+        // tree releases replaced value, objects pointing to it hold their own references
+        if let Some(ref locations) = self.value.locations {
+            locations.forget(&self.value);
+        }
+
+        match self.slot {
+            Slot::Root => *self.root = value.build(),
+            Slot::Key(ref parent, ref key) => unsafe {
+                let key_ptr = key.as_ptr() as *const c_char;
+                let elt = value.into_ptr();
+                if !ucl_object_replace_key(parent.obj as *mut _, elt, key_ptr, key.len(), true) {
+                    return Err(Error::Internal)
+                }
+            },
+            Slot::Index(ref parent, idx) => unsafe {
+                let elt = value.into_ptr();
+                let old = ucl_array_replace_index(parent.obj as *mut _, elt, idx as c_uint);
+                if old.is_null() {
+                    ucl_object_unref(elt);
+                    return Err(Error::Internal)
+                }
+                ucl_object_unref(old);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Deref for ObjectMut<'a> {
+    type Target = Object;
+
+    fn deref(&self) -> &Object {
+        &self.value
+    }
+}
+
+impl<'a> DerefMut for ObjectMut<'a> {
+    fn deref_mut(&mut self) -> &mut Object {
+        &mut self.value
+    }
+}
+
+impl Object {
+    /// Look up value by JSON Pointer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::Parser::new().parse(r#"
+    ///     section { hosts = [ { port = 9000 }, { port = 9090 } ] }
+    ///     "a/b" = 1;
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(obj.pointer("/section/hosts/1/port").unwrap().as_int(), Some(9090));
+    /// assert_eq!(obj.pointer("/a~1b").unwrap().as_int(), Some(1));
+    /// assert!(obj.pointer("/section/hosts/2").is_none());
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<Object> {
+        if pointer.is_empty() { return Some(self.share()) }
+        if !pointer.starts_with('/') { return None }
+
+        pointer.split('/')
+            .skip(1)
+            .map(unescape)
+            .try_fold((self.share(), true), |(target, whole), token| target.token(&token, whole))
+            .map(|(target, _)| target)
+    }
+
+    /// Look up value by JSON Pointer for modification.
+    ///
+    /// Values inside implicit arrays cannot be modified, so `None` is returned for them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut obj = ucl::Parser::new().parse("section { number = 10k; }").unwrap();
+    ///
+    /// obj.pointer_mut("/section/number").unwrap().set("unlimited").unwrap();
+    ///
+    /// assert_eq!(obj.fetch_path("section.number").unwrap().as_string(), Some("unlimited".to_string()));
+    /// ```
+    pub fn pointer_mut<'a>(&'a mut self, pointer: &str) -> Option<ObjectMut<'a>> {
+        if pointer.is_empty() {
+            let value = self.share();
+
+            return Some(ObjectMut {
+                root: self,
+                slot: Slot::Root,
+                value: value
+            })
+        }
+
+        let split = pointer.rfind('/')?;
+        let parent = self.pointer(&pointer[..split])?;
+        let token = unescape(&pointer[split + 1..]);
+
+        if parent.is_implicit_array() { return None }
+
+        let (value, _) = parent.token(&token, true)?;
+        let slot = match parent.get_type() {
+            Type::Array => Slot::Index(parent, parse_index(&token)?),
+            _ => Slot::Key(parent, token)
+        };
+
+        Some(ObjectMut {
+            root: self,
+            slot: slot,
+            value: value
+        })
+    }
+
+    /// Resolve single reference token. `whole` is `false` for values taken out of implicit
+    /// array, which share pointer with the rest of the array.
+    fn token(&self, token: &str, whole: bool) -> Option<(Object, bool)> {
+        if whole && self.is_implicit_array() {
+            return parse_index(token).and_then(|idx| self.values().nth(idx)).map(|val| (val, false))
+        }
+
+        let found = match self.get_type() {
+            Type::Object => unsafe {
+                ucl_object_find_keyl(self.obj, token.as_ptr() as *const c_char, token.len())
+            },
+            Type::Array => {
+                let idx = parse_index(token)?;
+                if idx > c_uint::max_value() as usize { return None }
+
+                unsafe { ucl_array_find_index(self.obj, idx as c_uint) }
+            },
+            _ => return None
+        };
+
+        self.child(found).map(|val| (val, true))
+    }
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+fn parse_index(token: &str) -> Option<usize> {
+    if token.starts_with('+') || (token.starts_with('0') && token.len() > 1) {
+        return None
+    }
+
+    token.parse().ok()
+}
@@ -0,0 +1,250 @@
+//! Selectors for querying object trees.
+//!
+//! Selector is a list of steps delimited by `.` (dot):
+//!
+//! - `key` - value stored under key, `\` escapes next character (i.e. `a\.b` is key `a.b`)
+//! - `"key"` or `'key'` - quoted key, may contain any character
+//! - `*` - all values of object or elements of array
+//! - `[n]` - `n`-th element of array
+//! - `[*]` - all elements of array
+//! - `["key"]` - quoted key, same as `."key"`
+//!
+//! Values stored under non-unique keys (implicit arrays) are treated as arrays of their values,
+//! so `hosts[1]` and `hosts[*]` address them by index, and `hosts.port` selects key in every
+//! value.
+
+use libc::c_char;
+use libucl_sys::*;
+
+use error::Error;
+use super::{
+    Object,
+    Type
+};
+use Result;
+
+use std::iter::Peekable;
+use std::str::Chars;
+use std::vec;
+
+/// Single step of selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Value stored under key
+    Key(String),
+    /// Element of array
+    Index(usize),
+    /// All values of object or elements of array
+    All
+}
+
+/// Compiled selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    steps: Vec<Step>
+}
+
+/// Iterator over values matched by selector.
+pub struct Matches {
+    inner: vec::IntoIter<Object>
+}
+
+impl Iterator for Matches {
+    type Item = Object;
+
+    fn next(&mut self) -> Option<Object> {
+        self.inner.next()
+    }
+}
+
+impl Selector {
+    /// Parse selector.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ucl::object::query::Selector;
+    ///
+    /// assert!(Selector::parse("section.hosts[*].port").is_ok());
+    /// assert!(Selector::parse("section..port").is_err());
+    /// ```
+    pub fn parse<T: AsRef<str>>(selector: T) -> Result<Self> {
+        let selector = selector.as_ref();
+        let invalid = |msg: &str| Error::InvalidSelector(format!("{}: {}", msg, selector));
+
+        let mut steps = Vec::new();
+        let mut chars = selector.chars().peekable();
+
+        loop {
+            match chars.peek().cloned() {
+                Some('[') => (),
+                Some('*') => { chars.next(); steps.push(Step::All) },
+                Some(quote @ '"') | Some(quote @ '\'') => {
+                    chars.next();
+                    steps.push(Step::Key(quoted(&mut chars, quote).ok_or_else(|| invalid("unterminated string"))?))
+                },
+                _ => {
+                    let key = bare(&mut chars).ok_or_else(|| invalid("trailing escape"))?;
+                    if key.is_empty() { return Err(invalid("empty key")) }
+                    steps.push(Step::Key(key))
+                }
+            }
+
+            while chars.peek() == Some(&'[') {
+                chars.next();
+                steps.push(bracket(&mut chars).ok_or_else(|| invalid("invalid index"))?);
+            }
+
+            match chars.next() {
+                None => break,
+                Some('.') => (),
+                Some(_) => return Err(invalid("expected `.`"))
+            }
+        }
+
+        Ok(Selector { steps: steps })
+    }
+
+    /// Return steps of selector.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Select matching values from given object.
+    pub fn select(&self, obj: &Object) -> Matches {
+        let mut nodes = vec![Node::whole(obj.share())];
+
+        for step in &self.steps {
+            nodes = nodes.iter().flat_map(|node| node.apply(step)).collect();
+        }
+
+        let values: Vec<_> = nodes.iter().flat_map(Node::values).collect();
+
+        Matches { inner: values.into_iter() }
+    }
+}
+
+impl Object {
+    /// Return iterator over values matching given selector.
+    ///
+    /// See `ucl::object::query` for selector syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let obj = ucl::Parser::new().parse(r#"
+    ///     hosts { host = "localhost"; port = 9000; }
+    ///     hosts { host = "remotehost"; port = 9090; }
+    ///     "a.b" = 1;
+    /// "#).unwrap();
+    ///
+    /// let ports: Vec<_> = obj.query("hosts[*].port").unwrap().filter_map(|o| o.as_int()).collect();
+    /// assert_eq!(ports, vec![9000, 9090]);
+    ///
+    /// let host = obj.query("hosts[1].host").unwrap().next().unwrap();
+    /// assert_eq!(host.as_string(), Some("remotehost".to_string()));
+    ///
+    /// assert_eq!(obj.query(r"a\.b").unwrap().next().unwrap().as_int(), Some(1));
+    /// ```
+    pub fn query<T: AsRef<str>>(&self, selector: T) -> Result<Matches> {
+        Selector::parse(selector).map(|selector| selector.select(self))
+    }
+}
+
+/// Intermediate match. `whole` is `false` for values taken out of implicit array, which share
+/// pointer with the rest of the array.
+struct Node {
+    obj: Object,
+    whole: bool
+}
+
+impl Node {
+    fn whole(obj: Object) -> Self {
+        Node { obj: obj, whole: true }
+    }
+
+    fn single(obj: Object) -> Self {
+        Node { obj: obj, whole: false }
+    }
+
+    fn values(&self) -> Vec<Object> {
+        if self.whole { self.obj.values().collect() } else { vec![self.obj.share()] }
+    }
+
+    fn implicit(&self) -> bool {
+        self.whole && self.obj.is_implicit_array()
+    }
+
+    fn apply(&self, step: &Step) -> Vec<Node> {
+        let obj = &self.obj;
+
+        match *step {
+            Step::Key(ref key) => self.values().into_iter()
+                .filter(|val| val.get_type() == Type::Object)
+                .filter_map(|val| {
+                    let found = unsafe {
+                        ucl_object_find_keyl(val.obj, key.as_ptr() as *const c_char, key.len())
+                    };
+
+                    val.child(found)
+                })
+                .map(Node::whole)
+                .collect(),
+            Step::Index(idx) if self.implicit() => obj.values().nth(idx).into_iter().map(Node::single).collect(),
+            Step::Index(idx) => match obj.get_type() {
+                Type::Array => obj.iter().nth(idx).into_iter().map(Node::whole).collect(),
+                _ => Vec::new()
+            },
+            Step::All if self.implicit() => obj.values().map(Node::single).collect(),
+            Step::All => match obj.get_type() {
+                Type::Array | Type::Object => obj.iter().map(Node::whole).collect(),
+                _ => Vec::new()
+            }
+        }
+    }
+}
+
+fn bare(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut key = String::new();
+
+    loop {
+        match chars.peek().cloned() {
+            None | Some('.') | Some('[') => return Some(key),
+            Some('\\') => {
+                chars.next();
+                key.push(chars.next()?);
+            },
+            Some(c) => { chars.next(); key.push(c) }
+        }
+    }
+}
+
+fn quoted(chars: &mut Peekable<Chars>, quote: char) -> Option<String> {
+    let mut key = String::new();
+
+    loop {
+        match chars.next()? {
+            '\\' => key.push(chars.next()?),
+            c if c == quote => return Some(key),
+            c => key.push(c)
+        }
+    }
+}
+
+fn bracket(chars: &mut Peekable<Chars>) -> Option<Step> {
+    let step = match chars.next()? {
+        '*' => Step::All,
+        quote @ '"' | quote @ '\'' => Step::Key(quoted(chars, quote)?),
+        c => {
+            let mut num = c.to_string();
+            while chars.peek().map_or(false, |c| *c != ']') { num.push(chars.next()?) }
+
+            Step::Index(num.trim().parse().ok()?)
+        }
+    };
+
+    match chars.next()? {
+        ']' => Some(step),
+        _ => None
+    }
+}
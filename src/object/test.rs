@@ -1,5 +1,11 @@
 use super::*;
 
+use Parser;
+
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+
 #[test]
 fn from_int() {
     let obj = Builder::from(10).build();
@@ -30,6 +36,60 @@ fn from_str() {
     assert_eq!(obj.get_type(), Type::String);
 }
 
+#[test]
+fn try_from_primitives() {
+    assert_eq!(Builder::try_from_int(10).unwrap().build().as_int(), Some(10));
+    assert_eq!(Builder::try_from_float(1.5).unwrap().build().as_float(), Some(1.5));
+    assert_eq!(Builder::try_from_bool(true).unwrap().build().as_bool(), Some(true));
+    assert_eq!(Builder::try_from_str("lol").unwrap().build().as_str(), Some("lol"));
+    assert_eq!(Builder::try_from_bytes(&b"a\0b"[..]).unwrap().build().as_bytes(), Some(&b"a\0b"[..]));
+}
+
+#[test]
+fn from_bytes() {
+    let obj = Builder::from(&b"a\0b"[..]).build();
+    assert_eq!(obj.get_type(), Type::String);
+    assert_eq!(obj.as_bytes(), Some(&b"a\0b"[..]));
+    assert_eq!(obj.as_str(), Some("a\0b"));
+    assert_eq!(obj.as_string(), Some("a\0b".to_string()));
+
+    let obj = Builder::from(&b"caf\xe9"[..]).build();
+    assert_eq!(obj.as_bytes(), Some(&b"caf\xe9"[..]));
+    assert_eq!(obj.as_str(), None);
+    assert_eq!(obj.as_string(), None);
+
+    let obj = Builder::from(&b""[..]).build();
+    assert_eq!(obj.as_bytes(), Some(&b""[..]));
+    assert_eq!(obj.as_str(), Some(""));
+
+    assert_eq!(Builder::from(1).build().as_bytes(), None);
+}
+
+#[test]
+fn bytes_hash_and_equality() {
+    let hash = |obj: &Object| {
+        let mut hasher = DefaultHasher::new();
+        obj.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let a = Builder::from(&b"\xff\xfe"[..]).build();
+    let b = Builder::from(&b"\xff\xfe"[..]).build();
+    assert_eq!(a, b);
+    assert_eq!(hash(&a), hash(&b));
+}
+
+#[test]
+fn frozen_bytes() {
+    let mut obj = Parser::new().parse("a = 1; b = lol;").unwrap();
+    obj.pointer_mut("/a").unwrap().set(&b"\xe9\0"[..]).unwrap();
+    let frozen = obj.freeze().unwrap();
+
+    assert_eq!(frozen.fetch("a").unwrap().as_bytes(), Some(&b"\xe9\0"[..]));
+    assert_eq!(frozen.fetch("a").unwrap().as_str(), None);
+    assert_eq!(frozen.fetch("b").unwrap().as_str(), Some("lol"));
+}
+
 #[test]
 fn to_int() {
     let obj = Builder::from(10).build();
@@ -47,3 +107,484 @@ fn to_int_invalid_type() {
     let obj = Builder::from(10.0f64).build();
     assert_eq!(obj.as_int(), None);
 }
+
+#[test]
+fn equality() {
+    let a = Parser::new().parse("a = 1; b { c = [1, 2, 3]; d = lol; }").unwrap();
+    let b = Parser::new().parse(r#"{"b": {"d": "lol", "c": [1, 2, 3]}, "a": 1}"#).unwrap();
+    let c = Parser::new().parse("a = 1; b { c = [1, 2, 4]; d = lol; }").unwrap();
+
+    assert_eq!(a, b);
+    assert!(a != c);
+}
+
+#[test]
+fn equality_different_types() {
+    assert!(Builder::from(1).build() != Builder::from(1.0f64).build());
+    assert!(Builder::from(1).build() != Builder::from("1").build());
+}
+
+#[test]
+fn ordering() {
+    assert!(Builder::from(1).build() < Builder::from(2).build());
+    assert!(Builder::from("a").build() < Builder::from("b").build());
+    assert_eq!(Builder::from(true).build().cmp(&Builder::from(true).build()), Ordering::Equal);
+}
+
+#[test]
+fn ordering_is_total() {
+    let build = |val: f64| Builder::from(val).build();
+    assert!(build(0.1) < build(0.5));
+    assert!(build(0.5) < build(0.9));
+    assert!(build(0.1) != build(0.9));
+
+    let min = Builder::from(i64::min_value()).build();
+    let max = Builder::from(i64::max_value()).build();
+    assert!(min < max);
+    assert!(max > min);
+
+    let a = Parser::new().parse("a = 1").unwrap();
+    let b = Parser::new().parse("b = 1").unwrap();
+    assert_eq!(a.cmp(&b), Ordering::Less);
+    assert_eq!(b.cmp(&a), Ordering::Greater);
+}
+
+#[test]
+fn ordering_differs_from_libucl() {
+    let libucl = |a: &Object, b: &Object| unsafe { ucl_object_compare(a.as_ptr(), b.as_ptr()) }.cmp(&0);
+
+    let (a, b) = (Builder::from(1.25f64).build(), Builder::from(1.5f64).build());
+    assert_eq!(libucl(&a, &b), Ordering::Equal);
+    assert_eq!(a.cmp(&b), Ordering::Less);
+
+    let (a, b) = (Builder::from("b").build(), Builder::from("aa").build());
+    assert_eq!(libucl(&a, &b), Ordering::Less);
+    assert_eq!(a.cmp(&b), Ordering::Greater);
+
+    let (a, b) = (Builder::from(&b"a\0b"[..]).build(), Builder::from(&b"a\0c"[..]).build());
+    assert_eq!(libucl(&a, &b), Ordering::Equal);
+    assert_eq!(a.cmp(&b), Ordering::Less);
+
+    let a = Parser::new().parse("a = 1").unwrap();
+    let b = Parser::new().parse("b = 1").unwrap();
+    assert_eq!(libucl(&a, &b), Ordering::Greater);
+    assert_eq!(libucl(&b, &a), Ordering::Greater);
+    assert_eq!(a.cmp(&b), Ordering::Less);
+}
+
+#[test]
+fn equality_implicit_arrays() {
+    let a = Parser::new().parse("a = 1; a = 2;").unwrap();
+    let b = Parser::new().parse("a = 1; a = 3;").unwrap();
+    let c = Parser::new().parse("a = 1;").unwrap();
+
+    assert!(a != b);
+    assert!(a != c);
+    assert!(c < a);
+}
+
+#[test]
+fn hashing() {
+    let mut set = HashSet::new();
+
+    set.insert(Parser::new().parse("a = 1; b = lol;").unwrap());
+    set.insert(Parser::new().parse("b = lol; a = 1;").unwrap());
+    set.insert(Parser::new().parse("a = 2; b = lol;").unwrap());
+
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn merge_replace() {
+    let mut obj = Parser::new().parse("a { b = 1; } c = [1];").unwrap();
+    let other = Parser::new().parse("a { d = 2; } c = [2]; e = 3;").unwrap();
+
+    obj.merge(&other, merge::REPLACE).unwrap();
+
+    assert!(obj.fetch_path("a.b").is_none());
+    assert_eq!(obj.fetch_path("a.d").unwrap().as_int(), Some(2));
+    assert_eq!(obj.fetch("c").unwrap().iter().count(), 1);
+    assert_eq!(obj.fetch("e").unwrap().as_int(), Some(3));
+}
+
+#[test]
+fn merge_while_iterating() {
+    let mut obj = Parser::new().parse("a = 1; b = [1, 2]; c = 3;").unwrap();
+    let other = Parser::new().parse("a = 2; b = [3]; d = 4; e = 5; f = 6; g = 7; h = 8;").unwrap();
+
+    let mut keys = obj.pointer("").unwrap().iter();
+    let mut values = obj.fetch("b").unwrap().iter();
+    assert!(keys.next().is_some());
+    assert_eq!(values.next().unwrap().as_int(), Some(1));
+
+    obj.merge(&other, merge::APPEND_ARRAYS).unwrap();
+
+    assert_eq!(keys.count(), 2);
+    assert_eq!(values.map(|v| v.as_int().unwrap()).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(obj.iter().count(), 8);
+}
+
+#[test]
+fn merge_deep() {
+    let mut obj = Parser::new().parse("a { b = 1; c = 1; }").unwrap();
+    let other = Parser::new().parse("a { c = 2; d = 2; }").unwrap();
+
+    obj.merge(&other, merge::DEEP_MERGE).unwrap();
+
+    assert_eq!(obj.fetch_path("a.b").unwrap().as_int(), Some(1));
+    assert_eq!(obj.fetch_path("a.c").unwrap().as_int(), Some(2));
+    assert_eq!(obj.fetch_path("a.d").unwrap().as_int(), Some(2));
+    assert_eq!(other.fetch_path("a.b"), None);
+}
+
+#[test]
+fn merge_append_arrays() {
+    let mut obj = Parser::new().parse("a = [1, 2]").unwrap();
+    let other = Parser::new().parse("a = [3]").unwrap();
+
+    obj.merge(&other, merge::APPEND_ARRAYS).unwrap();
+
+    let values: Vec<_> = obj.fetch("a").unwrap().iter().map(|v| v.as_int().unwrap()).collect();
+    assert_eq!(values, vec![1, 2, 3]);
+    assert_eq!(other.fetch("a").unwrap().iter().count(), 1);
+}
+
+#[test]
+fn merge_priority() {
+    let mut obj = Parser::new().parse(".priority 5\na = 1; b = 1;").unwrap();
+    let other = Parser::new().parse(".priority 1\na = 2; c = 2;").unwrap();
+
+    obj.merge(&other, merge::PRIORITY).unwrap();
+
+    assert_eq!(obj.fetch("a").unwrap().as_int(), Some(1));
+    assert_eq!(obj.fetch("c").unwrap().as_int(), Some(2));
+
+    let high = Parser::new().parse(".priority 9\nb = 3;").unwrap();
+    obj.merge(&high, merge::PRIORITY).unwrap();
+
+    assert_eq!(obj.fetch("b").unwrap().as_int(), Some(3));
+}
+
+#[test]
+fn merge_type_mismatch() {
+    let mut obj = Builder::from(1).build();
+    let other = Parser::new().parse("a = 1").unwrap();
+
+    match obj.merge(&other, merge::REPLACE) {
+        Err(Error::TypeMismatch(_)) => (),
+        res => panic!("unexpected result: {:?}", res)
+    }
+}
+
+#[test]
+fn deep_clone() {
+    let doc = Parser::new().parse("a { b = [1, 2]; c { d = 1; } }").unwrap();
+    let orig = doc.fetch("a").unwrap();
+    let mut copy = orig.deep_clone().unwrap();
+
+    assert_eq!(copy, orig);
+
+    let other = Parser::new().parse("b = [3]; c { d = 2; }").unwrap();
+    copy.merge(&other, merge::DEEP_MERGE | merge::APPEND_ARRAYS).unwrap();
+
+    assert_eq!(copy.fetch("b").unwrap().iter().count(), 3);
+    assert_eq!(copy.fetch_path("c.d").unwrap().as_int(), Some(2));
+    assert_eq!(doc.fetch_path("a.b").unwrap().iter().count(), 2);
+    assert_eq!(doc.fetch_path("a.c.d").unwrap().as_int(), Some(1));
+}
+
+#[test]
+fn deep_clone_implicit_array() {
+    let doc = Parser::new().parse("a = 1; a = 2;").unwrap();
+    let copy = doc.fetch("a").unwrap().deep_clone().unwrap();
+
+    assert_eq!(copy.values().count(), 2);
+}
+
+#[test]
+fn query_index_and_wildcard() {
+    let obj = Parser::new().parse("a { list = [ { port = 1 }, { port = 2 } ] }").unwrap();
+
+    let ports: Vec<_> = obj.query("a.list[*].port").unwrap().filter_map(|o| o.as_int()).collect();
+    assert_eq!(ports, vec![1, 2]);
+    assert_eq!(obj.query("a.list[1].port").unwrap().next().unwrap().as_int(), Some(2));
+    assert!(obj.query("a.list[2].port").unwrap().next().is_none());
+}
+
+#[test]
+fn query_implicit_arrays() {
+    let obj = Parser::new().parse("hosts { port = 1 } hosts { port = 2 } a = 1; a = 2;").unwrap();
+
+    let ports: Vec<_> = obj.query("hosts.port").unwrap().filter_map(|o| o.as_int()).collect();
+    assert_eq!(ports, vec![1, 2]);
+    let first: Vec<_> = obj.query("hosts[0].port").unwrap().filter_map(|o| o.as_int()).collect();
+    assert_eq!(first, vec![1]);
+    assert_eq!(obj.query("a[0]").unwrap().count(), 1);
+    assert_eq!(obj.query("a").unwrap().count(), 2);
+}
+
+#[test]
+fn query_escaped_keys() {
+    let obj = Parser::new().parse(r#""a.b" { "c[d]" = 1; } x = { y = 2; z = 3; }"#).unwrap();
+
+    assert_eq!(obj.query(r"a\.b.c\[d\]").unwrap().next().unwrap().as_int(), Some(1));
+    assert_eq!(obj.query(r#""a.b"["c[d]"]"#).unwrap().next().unwrap().as_int(), Some(1));
+    assert_eq!(obj.query("x.*").unwrap().count(), 2);
+}
+
+#[test]
+fn query_invalid() {
+    assert!(Selector::parse("").is_err());
+    assert!(Selector::parse("a..b").is_err());
+    assert!(Selector::parse("a[1").is_err());
+    assert!(Selector::parse("a[x]").is_err());
+    assert!(Selector::parse("\"a").is_err());
+    assert!(Selector::parse("a[0]b").is_err());
+}
+
+#[test]
+fn pointer() {
+    let obj = Parser::new().parse(r#"a { b = [1, { c = 2 }]; } "d/e" = 3; "f~g" = 4; h = 5; h = 6;"#).unwrap();
+
+    assert_eq!(obj.pointer("").unwrap(), obj);
+    assert_eq!(obj.pointer("/a/b/0").unwrap().as_int(), Some(1));
+    assert_eq!(obj.pointer("/a/b/1/c").unwrap().as_int(), Some(2));
+    assert_eq!(obj.pointer("/d~1e").unwrap().as_int(), Some(3));
+    assert_eq!(obj.pointer("/f~0g").unwrap().as_int(), Some(4));
+    assert_eq!(obj.pointer("/h/1").unwrap().as_int(), Some(6));
+    assert_eq!(obj.pointer("/h/0").unwrap().as_int(), Some(5));
+}
+
+#[test]
+fn pointer_invalid() {
+    let obj = Parser::new().parse("a { b = [1, 2]; }").unwrap();
+
+    assert!(obj.pointer("a").is_none());
+    assert!(obj.pointer("/a/b/01").is_none());
+    assert!(obj.pointer("/a/b/+1").is_none());
+    assert!(obj.pointer("/a/b/2").is_none());
+    assert!(obj.pointer("/a/c").is_none());
+    assert!(obj.pointer("/a/b/0/c").is_none());
+}
+
+#[test]
+fn pointer_mut() {
+    let mut obj = Parser::new().parse("a { b = [1, 2]; c = 3; }").unwrap();
+
+    obj.pointer_mut("/a/b/0").unwrap().set(10).unwrap();
+    obj.pointer_mut("/a/c").unwrap().set("lol").unwrap();
+
+    assert_eq!(obj.pointer("/a/b/0").unwrap().as_int(), Some(10));
+    assert_eq!(obj.pointer("/a/b/1").unwrap().as_int(), Some(2));
+    assert_eq!(obj.pointer("/a/c").unwrap().as_string(), Some("lol".to_string()));
+    assert!(obj.pointer_mut("/a/d").is_none());
+
+    obj.pointer_mut("").unwrap().set(true).unwrap();
+    assert_eq!(obj.as_bool(), Some(true));
+}
+
+#[test]
+fn replaced_values_stay_valid() {
+    let mut obj = Parser::new().parse("a { b = 1; } c = [1, 2]; d = 1; d = 2;").unwrap();
+    let a = obj.fetch("a").unwrap();
+    let b = obj.fetch_path("a.b").unwrap();
+    let c = obj.pointer("/c/1").unwrap();
+    let d = obj.fetch("d").unwrap().values().nth(1).unwrap();
+
+    obj.pointer_mut("/a").unwrap().set(1).unwrap();
+    obj.pointer_mut("/c/1").unwrap().set(3).unwrap();
+    obj.pointer_mut("/d").unwrap().set(3).unwrap();
+    obj.pointer_mut("").unwrap().set(true).unwrap();
+    drop(obj);
+
+    assert_eq!(a.fetch("b").unwrap().as_int(), Some(1));
+    assert_eq!(b.as_int(), Some(1));
+    assert_eq!(c.as_int(), Some(2));
+    assert_eq!(d.as_int(), Some(2));
+    assert_eq!(d.values().count(), 1);
+}
+
+#[test]
+fn frozen_is_independent() {
+    let mut obj = Parser::new().parse("a { b = 1; }").unwrap();
+    let frozen = obj.freeze().unwrap();
+
+    obj.pointer_mut("/a/b").unwrap().set(2).unwrap();
+    assert_eq!(frozen.fetch_path("a.b").unwrap().as_int(), Some(1));
+
+    let mut thawed = frozen.thaw().unwrap();
+    thawed.pointer_mut("/a/b").unwrap().set(3).unwrap();
+    assert_eq!(frozen.fetch_path("a.b").unwrap().as_int(), Some(1));
+    assert_eq!(thawed.fetch_path("a.b").unwrap().as_int(), Some(3));
+}
+
+#[test]
+fn frozen_shared_between_threads() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let doc = "hosts { port = 9000; } hosts { port = 9090; } name = lol;";
+    let frozen = Arc::new(Parser::new().parse(doc).unwrap().freeze().unwrap());
+
+    let handles: Vec<_> = (0..8).map(|_| {
+        let frozen = frozen.clone();
+        thread::spawn(move || {
+            for _ in 0..100 {
+                let ports: Vec<_> = frozen.query("hosts[*].port").unwrap().filter_map(|o| o.as_int()).collect();
+                assert_eq!(ports, vec![9000, 9090]);
+                assert_eq!(frozen.fetch("name").unwrap().as_string(), Some("lol".to_string()));
+                assert_eq!(frozen.iter().count(), 2);
+            }
+            // child handles keep the tree alive after the root is dropped
+            frozen.fetch("hosts").unwrap()
+        })
+    }).collect();
+
+    let children: Vec<_> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    drop(frozen);
+
+    for child in children {
+        assert_eq!(child.values().count(), 2);
+    }
+}
+
+#[test]
+fn userdata_downcast() {
+    use std::net::IpAddr;
+
+    let addr: IpAddr = "::1".parse().unwrap();
+    let mut obj = Parser::new().parse("a = 1").unwrap();
+    obj.pointer_mut("/a").unwrap().set(Builder::userdata(addr).unwrap()).unwrap();
+
+    let val = obj.fetch("a").unwrap();
+    assert_eq!(val.get_type(), Type::UserData);
+    assert_eq!(val.downcast_ref::<IpAddr>(), Some(&addr));
+    assert_eq!(val.downcast_ref::<String>(), None);
+    assert_eq!(Builder::from(1).build().downcast_ref::<IpAddr>(), None);
+
+    let copy = obj.deep_clone().unwrap();
+    assert_eq!(copy.fetch("a").unwrap().downcast_ref::<IpAddr>(), Some(&addr));
+    let frozen = obj.freeze().unwrap();
+    assert_eq!(frozen.fetch("a").unwrap().downcast_ref::<IpAddr>(), Some(&addr));
+}
+
+#[test]
+fn userdata_emit() {
+    let mut obj = Parser::new().parse("a = 1").unwrap();
+    obj.pointer_mut("/a").unwrap().set(Builder::userdata(42u8).unwrap()).unwrap();
+
+    assert_eq!(Emitter::JSONCompact.emit(&obj).unwrap(), "{\"a\":\"42\"}");
+}
+
+#[test]
+fn userdata_dropped_once() {
+    use std::fmt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl fmt::Display for Counted {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmt, "counted")
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut obj = Parser::new().parse("a = 1").unwrap();
+    obj.pointer_mut("/a").unwrap().set(Builder::userdata(Counted).unwrap()).unwrap();
+
+    let frozen = obj.freeze().unwrap();
+    assert!(frozen.fetch("a").unwrap().downcast_ref::<Counted>().is_some());
+    drop(frozen);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+    obj.pointer_mut("/a").unwrap().set(1).unwrap();
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn userdata_merged() {
+    use std::fmt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl fmt::Display for Counted {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            write!(fmt, "counted")
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut other = Parser::new().parse("a = 1; b { c = 2; }; d = [1]").unwrap();
+    other.pointer_mut("/a").unwrap().set(Builder::userdata(Counted).unwrap()).unwrap();
+    other.pointer_mut("/b/c").unwrap().set(Builder::userdata(Counted).unwrap()).unwrap();
+    other.pointer_mut("/d/0").unwrap().set(Builder::userdata(Counted).unwrap()).unwrap();
+
+    let mut obj = Parser::new().parse("b { c = 1; }; d = [0]").unwrap();
+    obj.merge(&other, merge::DEEP_MERGE | merge::APPEND_ARRAYS).unwrap();
+
+    assert!(obj.pointer("/a").unwrap().downcast_ref::<Counted>().is_some());
+    assert!(obj.pointer("/b/c").unwrap().downcast_ref::<Counted>().is_some());
+    assert!(obj.pointer("/d/1").unwrap().downcast_ref::<Counted>().is_some());
+
+    drop(other);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+
+    drop(obj);
+    assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn error_alloc() {
+    match Builder::from_ptr(ptr::null_mut()) {
+        Err(Error::Alloc) => (),
+        res => panic!("unexpected result: {:?}", res.map(|b| b.build()))
+    }
+}
+
+#[test]
+fn error_emit() {
+    let obj = Builder::from(&b"\xff\xfe"[..]).build();
+
+    match Emitter::JSON.emit(&obj) {
+        Err(Error::Emit(_)) => (),
+        res => panic!("unexpected result: {:?}", res)
+    }
+}
+
+#[test]
+fn emitter_from_raw() {
+    assert_eq!(Emitter::try_from(ucl_emitter::UCL_EMIT_YAML).unwrap(), Emitter::YAML);
+
+    match Emitter::try_from(ucl_emitter::UCL_EMIT_MSGPACK) {
+        Err(Error::Other) => (),
+        res => panic!("unexpected result: {:?}", res)
+    }
+}
+
+#[test]
+fn error_invalid_key() {
+    match Parser::new().register_var("A\0B".to_string(), "lol".to_string()) {
+        Err(Error::InvalidKey(_)) => (),
+        res => panic!("unexpected result: {:?}", res)
+    }
+
+    let obj = Parser::new().parse("a { b = 1; }").unwrap();
+    assert!(obj.fetch("a\0b").is_none());
+    assert!(obj.fetch_path("a\0.b").is_none());
+}
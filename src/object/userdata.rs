@@ -0,0 +1,138 @@
+//! User data objects holding Rust values.
+//!
+//! Values are shared by all copies of object made by `Object::deep_clone` and `Object::freeze`,
+//! and dropped when the last copy is freed by libucl. They are emitted as strings formatted with
+//! their `Display` implementation.
+
+use libucl_sys::*;
+use libc::{
+    c_char,
+    c_void
+};
+
+use super::{
+    Builder,
+    Object,
+    Type
+};
+use Result;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::fmt::Display;
+use std::sync::Arc;
+
+trait Value: Any + Display + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Display + Send + Sync> Value for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Payload of user data object, passed to libucl as pointer created by `Arc::into_raw`.
+struct Holder {
+    value: Box<dyn Value>
+}
+
+thread_local! {
+    // libucl copies emitted string right away, so it only needs to outlive emitter call
+    static RENDERED: RefCell<CString> = RefCell::new(CString::default());
+}
+
+unsafe extern "C" fn dtor(ptr: *mut c_void) {
+    drop(Arc::from_raw(ptr as *const Holder));
+}
+
+unsafe extern "C" fn emitter(ptr: *mut c_void) -> *const c_char {
+    let holder = &*(ptr as *const Holder);
+    // libucl measures string with `strlen`
+    let rendered = CString::new(holder.value.to_string().replace('\0', "")).unwrap_or_default();
+
+    RENDERED.with(|cell| {
+        let ptr = rendered.as_ptr();
+        *cell.borrow_mut() = rendered;
+        ptr
+    })
+}
+
+/// Return payload of user data object created by `Builder::userdata`.
+fn holder(obj: *const ucl_object_t) -> Option<*const Holder> {
+    unsafe {
+        if Type::from(ucl_object_type(obj)) != Type::UserData { return None }
+
+        let ud = obj as *const ucl_object_userdata;
+        let ours = dtor as unsafe extern "C" fn(*mut c_void);
+        match (*ud).dtor {
+            Some(func) if func as usize == ours as usize => Some((*ud).obj.uv() as *const Holder),
+            _ => None
+        }
+    }
+}
+
+/// Return reference to value of type `T` held by user data object. Internal use only.
+///
+/// Reference must not outlive the tree containing object.
+pub unsafe fn downcast_ref<'a, T: Any>(obj: *const ucl_object_t) -> Option<&'a T> {
+    holder(obj).and_then(|holder| (*holder).value.as_any().downcast_ref())
+}
+
+/// Take reference to every value held in tree created by `ucl_object_copy`, which copies user
+/// data objects together with their destructors. Internal use only.
+pub fn retain_copied(obj: &Object) {
+    for val in obj.values() {
+        match val.get_type() {
+            Type::UserData => if let Some(holder) = holder(val.obj) {
+                unsafe { Arc::increment_strong_count(holder) }
+            },
+            Type::Object | Type::Array => for child in val.iter() { retain_copied(&child) },
+            _ => ()
+        }
+    }
+}
+
+impl Builder {
+    /// Create user data object holding given value.
+    ///
+    /// Value is dropped when libucl frees the object. Objects are emitted as strings formatted
+    /// with `Display`. Values must be `Send` and `Sync`, as trees can be shared between threads
+    /// with `Object::freeze`.
+    ///
+    /// Fails with `Error::Alloc` if libucl cannot allocate the object, value is dropped then.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::net::IpAddr;
+    /// use ucl::object::{Builder, Emitter};
+    ///
+    /// let addr: IpAddr = "127.0.0.1".parse().unwrap();
+    /// let obj = Builder::userdata(addr).unwrap().build();
+    ///
+    /// assert_eq!(obj.downcast_ref::<IpAddr>(), Some(&addr));
+    /// assert_eq!(Emitter::JSON.emit(&obj).unwrap(), "\"127.0.0.1\"");
+    /// ```
+    pub fn userdata<T: Any + Display + Send + Sync>(value: T) -> Result<Self> {
+        let holder = Arc::into_raw(Arc::new(Holder { value: Box::new(value) }));
+
+        let obj = unsafe { ucl_object_new_userdata(Some(dtor), Some(emitter), holder as *mut c_void) };
+
+        Builder::from_ptr(obj).map_err(|err| {
+            drop(unsafe { Arc::from_raw(holder) });
+            err
+        })
+    }
+}
+
+impl Object {
+    /// Return reference to value held by user data object if it is of type `T`.
+    ///
+    /// Returns `None` for other objects, including user data objects not created by
+    /// `Builder::userdata`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        unsafe { downcast_ref(self.obj) }
+    }
+}
@@ -1,15 +1,53 @@
 use libucl_sys::*;
-use libc::size_t;
+use libc::{
+    self,
+    c_char,
+    c_uchar,
+    c_uint,
+    c_void,
+    size_t
+};
 
 use utils;
 use error;
+use include::{
+    FsResolver,
+    IncludeResolver
+};
+use location::{
+    self,
+    Entry,
+    Source
+};
 use super::Result;
 use object::{
     self,
-    Object
+    Object,
+    Type
 };
 
-use std::path::Path;
+use std::cmp;
+use std::fs::{
+    self,
+    File
+};
+use std::path::{
+    Path,
+    PathBuf
+};
+use std::ptr;
+use std::rc::Rc;
+use std::result;
+use std::slice;
+use std::str;
+use std::sync::Arc;
+use std::usize;
+
+/// Highest priority supported by libucl.
+const MAX_PRIORITY: usize = 15;
+
+/// Deepest nesting of documents included by custom `.include` resolution.
+const MAX_INCLUDE_DEPTH: usize = 16;
 
 bitflags! {
     flags Flags: i32 {
@@ -17,12 +55,234 @@ bitflags! {
         const LOWERCASE          = 0x1,
         const ZEROCOPY           = 0x2,
         const NO_TIME            = 0x4,
-        const NO_IMPLICIT_ARRAYS = 0x8
+        const NO_IMPLICIT_ARRAYS = 0x8,
+        const SAVE_COMMENTS      = 0x10,
+        const DISABLE_MACRO      = 0x20,
+        const NO_FILEVARS        = 0x40
     }
 }
 
+/// Limits applied to parsed documents.
+///
+/// `size` is checked before a document is passed to libucl. It applies to every parsed string
+/// or file and to every document loaded by custom `.include` resolution (see `include` module),
+/// but not to documents included by libucl itself. `depth` and `objects` are checked only after
+/// libucl has built the whole tree, so they reject documents exceeding them but do not bound
+/// memory or time spent parsing. Use `size` to bound those.
+///
+/// Default limits are unbounded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximal size of input in bytes
+    pub size: usize,
+    /// Maximal nesting depth of objects and arrays
+    pub depth: usize,
+    /// Maximal number of values in parsed tree
+    pub objects: usize
+}
+
+impl Limits {
+    /// Limits used by `Parser::untrusted`.
+    ///
+    /// Only `size` guards resources spent by libucl, as it is the only limit checked before
+    /// parsing. `depth` and `objects` merely reject parsed documents, so memory and time needed
+    /// to parse input of given size are bounded by `size` alone.
+    pub fn untrusted() -> Self {
+        Limits {
+            size: 1024 * 1024,
+            depth: 32,
+            objects: 16 * 1024
+        }
+    }
+
+    fn check_size(&self, size: usize) -> Result<()> {
+        if size > self.size {
+            Err(error::Error::TooLarge(self.size))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_tree(&self, obj: &Object, depth: usize, count: &mut usize) -> Result<()> {
+        // nothing to check, walking the tree would only cost time
+        if self.depth == usize::MAX && self.objects == usize::MAX { return Ok(()) }
+
+        for val in obj.values() {
+            *count += 1;
+            if *count > self.objects { return Err(error::Error::TooManyObjects(self.objects)) }
+
+            match val.get_type() {
+                Type::Object | Type::Array if depth > self.depth => {
+                    return Err(error::Error::TooDeep(self.depth))
+                },
+                _ => ()
+            }
+
+            for child in val.iter() {
+                self.check_tree(&child, depth + 1, count)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            size: usize::MAX,
+            depth: usize::MAX,
+            objects: usize::MAX
+        }
+    }
+}
+
+/// State of custom `.include` resolution shared with macro handlers.
+struct Includes {
+    parser: *mut ucl_parser,
+    resolver: Box<dyn IncludeResolver>,
+    paths: Vec<PathBuf>,
+    error: Option<error::Error>,
+    /// Copy of `Limits::size`
+    size: usize,
+    sources: Option<Vec<Option<Source>>>,
+    files: Vec<PathBuf>,
+    /// Canonical paths of documents being loaded, outermost first
+    loading: Vec<PathBuf>
+}
+
+impl Includes {
+    /// Resolve and load included document. Returns `None` if document should be skipped.
+    fn load(&mut self, data: &[u8], args: Option<Object>, must_exist: bool) -> result::Result<Option<(PathBuf, Vec<u8>, usize)>, String> {
+        let path = match str::from_utf8(data) {
+            Ok(path) => path,
+            Err(_) => return Err(format!("invalid include path: {}", String::from_utf8_lossy(data)))
+        };
+
+        let priority = args.as_ref()
+            .and_then(|args| args.fetch("priority"))
+            .and_then(|prio| prio.as_int())
+            .unwrap_or(0);
+        if priority < 0 || priority > MAX_PRIORITY as i64 {
+            return Err(format!("invalid priority {} for {}, must be between 0 and {}", priority, path, MAX_PRIORITY))
+        }
+        let priority = priority as usize;
+        let must_exist = must_exist && !args.as_ref()
+            .and_then(|args| args.fetch("try"))
+            .and_then(|soft| soft.as_bool())
+            .unwrap_or(false);
+
+        let loaded = match self.resolver.resolve(path, &self.paths) {
+            Some(resolved) => self.resolver.load(&resolved)
+                .map(|buf| (resolved, buf))
+                .map_err(|err| format!("cannot load {}: {}", path, err)),
+            None => Err(format!("cannot resolve {}", path))
+        };
+
+        match loaded {
+            Ok((resolved, buf)) => {
+                // documents of custom resolvers do not have to exist in filesystem
+                let canonical = fs::canonicalize(&resolved).unwrap_or_else(|_| resolved.clone());
+                if self.loading.contains(&canonical) {
+                    return Err(format!("include cycle: {} includes itself", resolved.display()))
+                }
+                if self.loading.len() >= MAX_INCLUDE_DEPTH {
+                    return Err(format!("cannot include {}: includes nested deeper than {}", resolved.display(), MAX_INCLUDE_DEPTH))
+                }
+
+                self.files.push(resolved.clone());
+
+                if let Some(ref mut sources) = self.sources {
+                    sources.push(Some(Source {
+                        file: Some(resolved),
+                        data: buf.clone(),
+                        priority: priority
+                    }));
+                }
+
+                Ok(Some((canonical, buf, priority)))
+            },
+            Err(_) if !must_exist => {
+                if let Some(ref mut sources) = self.sources {
+                    sources.push(None);
+                }
+
+                Ok(None)
+            },
+            Err(err) => Err(err)
+        }
+    }
+}
+
+fn include(ud: *mut c_void, data: *const c_uchar, len: size_t, args: *const ucl_object_t, must_exist: bool) -> bool {
+    let includes = ud as *mut Includes;
+    let data = unsafe { slice::from_raw_parts(data, len as usize) };
+
+    // `Includes` must not stay borrowed while parsing, as nested includes call handlers again
+    let loaded = unsafe { (*includes).load(data, Object::from_cptr(args), must_exist) };
+
+    match loaded {
+        Ok(Some((_, ref buf, _))) if buf.len() > unsafe { (*includes).size } => unsafe {
+            (*includes).error = Some(error::Error::TooLarge((*includes).size));
+            false
+        },
+        Ok(Some((canonical, buf, priority))) => unsafe {
+            (*includes).loading.push(canonical);
+            let res = ucl_parser_add_chunk_priority((*includes).parser,
+                                                    buf.as_ptr() as *const c_uchar,
+                                                    buf.len() as size_t,
+                                                    priority as c_uint);
+            (*includes).loading.pop();
+
+            res
+        },
+        Ok(None) => true,
+        Err(err) => {
+            unsafe { (*includes).error = Some(error::Error::Include(err)) };
+            false
+        }
+    }
+}
+
+extern "C" fn include_handler(data: *const c_uchar, len: size_t, args: *const ucl_object_t, ud: *mut c_void) -> bool {
+    include(ud, data, len, args, true)
+}
+
+extern "C" fn try_include_handler(data: *const c_uchar, len: size_t, args: *const ucl_object_t, ud: *mut c_void) -> bool {
+    include(ud, data, len, args, false)
+}
+
+/// Return first URL included by `.include` macro that must succeed.
+fn url_include(entries: &[Entry]) -> Option<String> {
+    entries.iter().filter_map(|entry| match *entry {
+        Entry::Include { ref path, required: true, .. } if path.starts_with("http://") || path.starts_with("https://") => {
+            Some(path.clone())
+        },
+        Entry::Value { ref children, .. } => url_include(children),
+        Entry::Include { .. } => None
+    }).next()
+}
+
+/// Record path of file included by libucl.
+extern "C" fn include_tracer(_parser: *mut ucl_parser,
+                             _parent: *const ucl_object_t,
+                             _args: *const ucl_object_t,
+                             path: *const c_char,
+                             len: size_t,
+                             ud: *mut c_void) {
+    let files = ud as *mut Vec<PathBuf>;
+    let path = unsafe { slice::from_raw_parts(path as *const u8, len as usize) };
+
+    unsafe { (*files).push(utils::path_from_bytes(path)) }
+}
+
 pub struct Parser {
     parser: *mut ucl_parser,
+    flags: Flags,
+    limits: Limits,
+    includes: Option<Box<Includes>>,
+    filename: Option<PathBuf>,
+    locations: bool
 }
 
 impl Parser {
@@ -41,6 +301,9 @@ impl Parser {
     ///   is not freed if an object is in use)
     /// - `NO_TIME` - do not parse time and treat it's value as string
     /// - `NO_IMPLICIT_ARRAYS` - create explicit arrays instead of implicit ones
+    /// - `SAVE_COMMENTS` - save comments in the parser context
+    /// - `DISABLE_MACRO` - treat macros (i.e. `.include`) as comments
+    /// - `NO_FILEVARS` - do not set `$FILENAME` and `$CURDIR` variables when parsing files
     ///
     /// # Examples
     ///
@@ -52,7 +315,53 @@ impl Parser {
     /// ```
     pub fn with_flags(flags: Flags) -> Self {
         Parser {
-            parser: unsafe { ucl_parser_new(flags.bits()) }
+            parser: unsafe { ucl_parser_new(flags.bits()) },
+            flags: flags,
+            limits: Limits::default(),
+            includes: None,
+            filename: None,
+            locations: false
+        }
+    }
+
+    /// Create new parser suitable for untrusted input
+    ///
+    /// Macros (`.include`, `.priority` and others, including URL fetching) are treated as
+    /// comments, file variables are not set and `Limits::untrusted` are applied. Only the size
+    /// limit is checked before parsing, see `Limits`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let doc = ucl::Parser::untrusted().parse(".include \"/etc/passwd\"\na = b").unwrap();
+    ///
+    /// assert_eq!(doc.fetch("a").unwrap().as_string(), Some("b".to_string()));
+    /// ```
+    pub fn untrusted() -> Self {
+        let mut parser = Self::with_flags(DISABLE_MACRO | NO_FILEVARS);
+        parser.set_limits(Limits::untrusted());
+
+        parser
+    }
+
+    /// Set limits for parsed documents
+    ///
+    /// Violations are reported as `Error::TooLarge`, `Error::TooDeep` and
+    /// `Error::TooManyObjects`. See `Limits` for when each limit is checked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut parser = ucl::Parser::new();
+    /// parser.set_limits(ucl::parser::Limits { depth: 1, ..Default::default() });
+    ///
+    /// assert!(parser.parse("a { b { c = d } }").is_err());
+    /// ```
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+
+        if let Some(ref mut includes) = self.includes {
+            includes.size = limits.size;
         }
     }
 
@@ -67,13 +376,17 @@ impl Parser {
     /// assert!(ucl::Parser::new().parse("a =").is_err());
     /// ```
     pub fn parse<T: AsRef<str>>(mut self, string: T) -> Result<Object> {
+        self.limits.check_size(string.as_ref().len())?;
+
         let len = string.as_ref().len() as size_t;
-        let result = unsafe { ucl_parser_add_chunk(self.parser, utils::to_c_str(string), len) };
+        let result = unsafe { ucl_parser_add_chunk(self.parser, string.as_ref().as_ptr(), len) };
 
         if result {
-            Ok(self.get_object().unwrap())
+            let obj = self.checked_object()?;
+            let file = self.filename.clone();
+            self.locate(obj, file, || Ok(string.as_ref().as_bytes().to_vec()))
         } else {
-            Err(self.get_error())
+            Err(self.get_error(|| Some(string.as_ref().as_bytes().to_vec())))
         }
     }
 
@@ -81,14 +394,185 @@ impl Parser {
     ///
     /// It moves out `Parser`.
     pub fn parse_file<T: AsRef<Path>>(mut self, path: T) -> Result<Object> {
-        let filename = path.as_ref().to_str().unwrap();
-        let result = unsafe { ucl_parser_add_file(self.parser, utils::to_c_str(filename)) };
+        self.load_file(path.as_ref())
+    }
 
-        if result {
-            Ok(self.get_object().unwrap())
-        } else {
-            Err(self.get_error())
+    /// Parse file at given `Path` and return it with paths of all files it included.
+    ///
+    /// Includes are handled by libucl, so globs and all macro parameters are supported, unless
+    /// custom `.include` resolution (see `include` module) was enabled with
+    /// `Parser::set_include_resolver` or `Parser::add_include_path`.
+    /// Then only documents loaded by `IncludeResolver` are returned.
+    ///
+    /// It moves out `Parser`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::fs;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("ucl-rs-parse-file-with-includes-{}", std::process::id()));
+    /// fs::create_dir_all(dir.join("conf.d")).unwrap();
+    /// fs::write(dir.join("main.conf"), ".include \"$CURDIR/conf.d/*.conf\"\nb = 2").unwrap();
+    /// fs::write(dir.join("conf.d/base.conf"), "a = 1").unwrap();
+    ///
+    /// let (doc, files) = ucl::Parser::new().parse_file_with_includes(dir.join("main.conf")).unwrap();
+    ///
+    /// assert_eq!(doc.fetch("a").unwrap().as_int(), Some(1));
+    /// assert_eq!(files, vec![fs::canonicalize(dir.join("conf.d/base.conf")).unwrap()]);
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn parse_file_with_includes<T: AsRef<Path>>(mut self, path: T) -> Result<(Object, Vec<PathBuf>)> {
+        if self.includes.is_some() {
+            let obj = self.load_file(path.as_ref())?;
+            return Ok((obj, self.includes().files.clone()))
         }
+
+        let mut files: Vec<PathBuf> = Vec::new();
+        unsafe {
+            let ud = &mut files as *mut Vec<PathBuf> as *mut c_void;
+            ucl_parser_set_include_tracer(self.parser, Some(include_tracer), ud);
+        }
+
+        let obj = self.load_file(path.as_ref());
+        // `files` does not outlive this call
+        unsafe { ucl_parser_set_include_tracer(self.parser, None, ptr::null_mut()) }
+
+        Ok((obj?, files))
+    }
+
+    /// Add every file in directory matching `glob`, in lexical order of file names.
+    ///
+    /// Files are parsed into the same root object as documents added later, so the result is
+    /// returned by `Parser::parse`, `Parser::parse_file` or `Parser::finish`. `glob` is matched
+    /// against file names and supports `*`, `?` and `[...]` character classes. Names starting
+    /// with dot are matched only if `glob` starts with dot too. Errors are reported as
+    /// `Error::File` with path of the failing file, and names that are not valid Unicode fail
+    /// with `Error::Conversion`.
+    ///
+    /// Source locations are not tracked for files added this way.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::fs;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("ucl-rs-add-dir-doc-{}", std::process::id()));
+    /// fs::create_dir_all(&dir).unwrap();
+    /// fs::write(dir.join("10-base.conf"), "port = 80; workers = 4").unwrap();
+    /// fs::write(dir.join("20-local.conf"), "port = 8080").unwrap();
+    /// fs::write(dir.join("README"), "not a config").unwrap();
+    ///
+    /// let mut parser = ucl::Parser::new();
+    /// parser.add_dir(&dir, "*.conf").unwrap();
+    /// let doc = parser.finish().unwrap();
+    ///
+    /// assert_eq!(doc.fetch("workers").unwrap().as_int(), Some(4));
+    /// assert_eq!(doc.fetch("port").unwrap().values().count(), 2);
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn add_dir<P: AsRef<Path>>(&mut self, dir: P, glob: &str) -> Result<()> {
+        self.load_dir(dir.as_ref(), glob, None)
+    }
+
+    /// Add every file in directory matching `glob` at increasing priorities.
+    ///
+    /// Same as `Parser::add_dir`, but `n`-th file is added with priority `first + n`, so values
+    /// from later files replace values from earlier ones. Priorities above 15, the highest
+    /// supported by libucl, are capped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::fs;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("ucl-rs-add-dir-priorities-doc-{}", std::process::id()));
+    /// fs::create_dir_all(&dir).unwrap();
+    /// fs::write(dir.join("10-base.conf"), "port = 80").unwrap();
+    /// fs::write(dir.join("20-local.conf"), "port = 8080").unwrap();
+    ///
+    /// let mut parser = ucl::Parser::new();
+    /// parser.add_dir_with_priorities(&dir, "*.conf", 0).unwrap();
+    /// let doc = parser.finish().unwrap();
+    ///
+    /// assert_eq!(doc.fetch("port").unwrap().as_int(), Some(8080));
+    /// assert_eq!(doc.fetch("port").unwrap().priority(), 1);
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn add_dir_with_priorities<P: AsRef<Path>>(&mut self, dir: P, glob: &str, first: usize) -> Result<()> {
+        self.load_dir(dir.as_ref(), glob, Some(first))
+    }
+
+    /// Return root object of all documents added so far.
+    ///
+    /// Returns empty object if no document was added. It moves out `Parser`.
+    pub fn finish(mut self) -> Result<Object> {
+        match self.get_object() {
+            Some(obj) => self.check(obj),
+            None => Ok(object::Builder::from_ptr(unsafe { ucl_object_typed_new(ucl_type_t::UCL_OBJECT) })?.build())
+        }
+    }
+
+    fn load_dir(&mut self, dir: &Path, glob: &str, first: Option<usize>) -> Result<()> {
+        let entries = fs::read_dir(dir).map_err(|err| error::Error::io(dir, &err))?;
+
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|err| error::Error::io(dir, &err))?.path();
+            let name = match path.file_name().map(|name| name.to_str()) {
+                Some(Some(name)) => name,
+                Some(None) => {
+                    return Err(error::Error::Conversion(format!("file name is not valid Unicode: {}", path.display())))
+                },
+                None => continue
+            };
+
+            // hidden files are matched only by patterns starting with dot, same as in shell
+            if name.starts_with('.') && !glob.starts_with('.') { continue }
+
+            if glob_match(glob, name) && path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        for (n, file) in files.into_iter().enumerate() {
+            let priority = first.map(|first| cmp::min(first.saturating_add(n), MAX_PRIORITY));
+
+            self.add_file(&file, priority).map_err(|err| error::Error::File(file, Box::new(err)))?;
+        }
+
+        Ok(())
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<Object> {
+        self.add_file(path, None)?;
+
+        let obj = self.checked_object()?;
+        let file = path.to_path_buf();
+        // libucl has read the file already, so it is read again only to scan locations
+        self.locate(obj, Some(file), || fs::read(path).map_err(|err| error::Error::io(path, &err)))
+    }
+
+    /// Add file with given priority, or with default priority if it is `None`.
+    fn add_file(&mut self, path: &Path, priority: Option<usize>) -> Result<()> {
+        let filename = utils::path_to_c_str(path)?;
+
+        // libucl reports only generic errors, so check the file is readable first
+        let meta = File::open(path).and_then(|file| file.metadata()).map_err(|err| error::Error::io(path, &err))?;
+        if meta.is_dir() {
+            return Err(error::Error::Io(Some(path.to_path_buf()), Some(libc::EISDIR)))
+        }
+        self.limits.check_size(meta.len() as usize)?;
+
+        let result = unsafe {
+            match priority {
+                Some(priority) => ucl_parser_add_file_priority(self.parser, filename.as_ptr(), priority as c_uint),
+                None => ucl_parser_add_file(self.parser, filename.as_ptr())
+            }
+        };
+
+        if result { Ok(()) } else { Err(self.get_error(|| fs::read(path).ok())) }
     }
 
     /// Register new variable
@@ -97,26 +581,249 @@ impl Parser {
     ///
     /// ```rust
     /// let p = ucl::Parser::new();
-    /// p.register_var("LOL".to_string(), "test".to_string());
+    /// p.register_var("LOL".to_string(), "test".to_string()).unwrap();
     /// let res = p.parse("lol = $LOL").unwrap();
     ///
     /// assert_eq!(res.fetch("lol").unwrap().as_string(), Some("test".to_string()));
     /// ```
-    pub fn register_var(&self, name: String, value: String) {
+    ///
+    /// Fails with `Error::InvalidKey` if name contains NUL byte or `Error::Conversion` if value
+    /// does.
+    pub fn register_var(&self, name: String, value: String) -> Result<()> {
+        let name = utils::to_c_str(&name).map_err(|_| error::Error::InvalidKey(name))?;
+        let value = utils::to_c_str(value)?;
+
         unsafe {
-            ucl_parser_register_variable(self.parser, utils::to_c_str(name), utils::to_c_str(value))
+            ucl_parser_register_variable(self.parser, name.as_ptr(), value.as_ptr())
+        }
+
+        Ok(())
+    }
+
+    /// Set `$FILENAME` and `$CURDIR` variables as if parsed string was loaded from `path`
+    ///
+    /// This allows documents kept in memory to include files relative to given location with
+    /// `.include "$CURDIR/file.conf"`. When `expand` is set, `path` is resolved to absolute
+    /// path with symbolic links expanded, so it must exist. Source locations of parsed string
+    /// refer to `path` too.
+    ///
+    /// Variables are set even with `NO_FILEVARS` flag, but they are replaced by parsed files.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut parser = ucl::Parser::new();
+    /// parser.set_filevars("/etc/app/app.conf", false).unwrap();
+    ///
+    /// let doc = parser.parse("dir = $CURDIR").unwrap();
+    ///
+    /// assert_eq!(doc.fetch("dir").unwrap().as_string(), Some("/etc/app".to_string()));
+    /// ```
+    pub fn set_filevars<P: AsRef<Path>>(&mut self, path: P, expand: bool) -> Result<()> {
+        let path = path.as_ref();
+        let path = if expand {
+            fs::canonicalize(path).map_err(|err| error::Error::io(path, &err))?
+        } else {
+            path.to_path_buf()
+        };
+        let filename = utils::path_to_c_str(&path)?;
+
+        if !unsafe { ucl_parser_set_filevars(self.parser, filename.as_ptr(), false) } {
+            return Err(error::Error::Io(Some(path), None))
+        }
+        self.filename = Some(path);
+
+        Ok(())
+    }
+
+    /// Set custom resolver for `.include` macros
+    ///
+    /// See `include` module for details.
+    pub fn set_include_resolver<R: IncludeResolver + 'static>(&mut self, resolver: R) {
+        self.includes().resolver = Box::new(resolver);
+    }
+
+    /// Add search path for relative paths in `.include` macros
+    ///
+    /// Paths are searched in order they were added. If no resolver was set, `FsResolver` is
+    /// used.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut parser = ucl::Parser::new();
+    /// parser.add_include_path("examples");
+    ///
+    /// let doc = parser.parse(".include \"test.conf\"").unwrap();
+    ///
+    /// assert_eq!(doc.fetch("lol").unwrap().as_string(), Some("lol".to_string()));
+    /// ```
+    pub fn add_include_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.includes().paths.push(path.as_ref().to_path_buf());
+    }
+
+    /// Enable tracking of source locations
+    ///
+    /// Locations are available through `Object::source_location`. Locations of included
+    /// documents are tracked only if they are loaded with custom `.include` resolution (see
+    /// `include` module), enabled with `Parser::set_include_resolver` or
+    /// `Parser::add_include_path`. Documents including files loaded by libucl itself fail to
+    /// parse with `Error::Untracked`, as do documents whose statements cannot be matched with
+    /// parsed values (see `location` module).
+    pub fn track_locations(&mut self) {
+        self.locations = true;
+
+        if let Some(ref mut includes) = self.includes {
+            includes.sources.get_or_insert_with(Vec::new);
+        }
+    }
+
+    fn locate<F: FnOnce() -> Result<Vec<u8>>>(&mut self, obj: Object, file: Option<PathBuf>, data: F) -> Result<Object> {
+        if !self.locations { return Ok(obj) }
+
+        let included = self.includes.as_mut().and_then(|includes| includes.sources.take());
+        let main = Source { file: file, data: data()?, priority: 0 };
+        let table = location::locate(&obj, &main, included.as_ref().map(|included| &included[..]), self.flags)?;
+
+        Ok(obj.with_locations(Some(Rc::new(table))))
+    }
+
+    fn includes(&mut self) -> &mut Includes {
+        let parser = self.parser;
+        let size = self.limits.size;
+        let sources = if self.locations { Some(Vec::new()) } else { None };
+        let fresh = self.includes.is_none();
+        let includes = self.includes.get_or_insert_with(|| Box::new(Includes {
+            parser: parser,
+            resolver: Box::new(FsResolver),
+            paths: Vec::new(),
+            error: None,
+            size: size,
+            sources: sources,
+            files: Vec::new(),
+            loading: Vec::new()
+        }));
+
+        if fresh {
+            let ud = &mut **includes as *mut Includes as *mut c_void;
+
+            unsafe {
+                ucl_parser_register_macro(parser, b"include\0".as_ptr() as *const c_char, Some(include_handler), ud);
+                ucl_parser_register_macro(parser, b"try_include\0".as_ptr() as *const c_char, Some(try_include_handler), ud);
+            }
         }
+
+        includes
+    }
+
+    fn checked_object(&mut self) -> Result<Object> {
+        let obj = self.get_object().ok_or(error::Error::Internal)?;
+        self.check(obj)
+    }
+
+    /// Check limits of parsed tree and attach saved comments.
+    fn check(&mut self, obj: Object) -> Result<Object> {
+        self.limits.check_tree(&obj, 0, &mut 0)?;
+
+        if !self.flags.contains(SAVE_COMMENTS) { return Ok(obj) }
+
+        let comments = object::Comments::from_cptr(unsafe { ucl_parser_get_comments(self.parser) });
+        Ok(obj.with_comments(comments.map(Arc::new)))
     }
 
     fn get_object(&mut self) -> Option<Object> {
-        object::Builder::from_ptr(unsafe { ucl_parser_get_object(self.parser) }).map(|o| o.build())
+        object::Builder::from_ptr(unsafe { ucl_parser_get_object(self.parser) }).ok().map(|o| o.build())
     }
 
-    fn get_error(&mut self) -> error::Error {
+    /// Return error of failed parser. `data` returns text of failed document.
+    fn get_error<F: FnOnce() -> Option<Vec<u8>>>(&mut self, data: F) -> error::Error {
+        if let Some(err) = self.includes.as_mut().and_then(|includes| includes.error.take()) {
+            return err
+        }
+
         let err = unsafe { ucl_parser_get_error_code(self.parser) };
-        let desc = unsafe { ucl_parser_get_error(self.parser) };
+        let desc = utils::to_str(unsafe { ucl_parser_get_error(self.parser) }).unwrap_or_default();
+
+        // libucl built without curl fails URL includes with generic error, so they are found in
+        // failed document instead, documents included by libucl are not checked
+        let native = self.includes.is_none() && !self.flags.contains(DISABLE_MACRO);
+        if !cfg!(feature = "urls") && native && err != ucl_error_t::UCL_ESYNTAX as i32 {
+            let source = data().map(|data| Source { file: None, data: data, priority: 0 });
+            if let Some(url) = source.and_then(|source| url_include(&location::scan(&source))) {
+                return error::Error::UrlsDisabled(url)
+            }
+        }
+
+        error::Error::from_code(err, desc)
+    }
+}
+
+/// Match file name against shell-like pattern with `*`, `?` and `[...]` (`[!...]` negates).
+fn glob_match(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut g, mut n) = (0, 0);
+    // position after last `*` and position in name it currently matches up to
+    let mut star = None;
 
-        error::Error::from_code(err, utils::to_str(desc).unwrap())
+    while n < name.len() {
+        if glob.get(g) == Some(&'*') {
+            g += 1;
+            star = Some((g, n));
+            continue
+        }
+
+        if let Some(next) = glob_match_char(&glob, g, name[n]) {
+            g = next;
+            n += 1;
+            continue
+        }
+
+        // let last `*` match one more character
+        match star {
+            Some((after, matched)) => {
+                g = after;
+                n = matched + 1;
+                star = Some((after, n));
+            },
+            None => return false
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// Match single character against glob element at `pos`. Returns position of next element.
+fn glob_match_char(glob: &[char], pos: usize, c: char) -> Option<usize> {
+    match glob.get(pos) {
+        None | Some(&'*') => None,
+        Some(&'?') => Some(pos + 1),
+        Some(&'[') => {
+            let negate = glob.get(pos + 1) == Some(&'!');
+            let start = if negate { pos + 2 } else { pos + 1 };
+            // `]` right after opening bracket is literal
+            let end = match glob.get(start + 1..).and_then(|rest| rest.iter().position(|&c| c == ']')) {
+                Some(end) => start + 1 + end,
+                None => return if c == '[' { Some(pos + 1) } else { None }
+            };
+
+            let class = &glob[start..end];
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= class[i] <= c && c <= class[i + 2];
+                    i += 3;
+                } else {
+                    found |= class[i] == c;
+                    i += 1;
+                }
+            }
+
+            if found != negate { Some(end + 1) } else { None }
+        },
+        Some(&l) => if l == c { Some(pos + 1) } else { None }
     }
 }
 
@@ -129,6 +836,16 @@ impl Drop for Parser {
 #[cfg(test)]
 mod test {
     use super::*;
+    use object::Emitter;
+    use utils::TempDir;
+
+    use std::collections::HashMap;
+    use std::fs::{
+        self,
+        File
+    };
+    use std::io::Write;
+    use std::path::PathBuf;
 
     #[test]
     fn string_parsing() {
@@ -169,13 +886,603 @@ mod test {
         assert_eq!(res.fetch("lol").unwrap().as_int(), Some(10));
     }
 
+    #[test]
+    fn flag_zerocopy() {
+        let s = r#"lol = "test"; arr = [1, 2]"#;
+        let res = Parser::with_flags(ZEROCOPY).parse(s).unwrap();
+
+        assert_eq!(res, Parser::new().parse(s).unwrap());
+    }
+
+    #[test]
+    fn flag_no_time() {
+        let s = r#"lol = 10min"#;
+
+        let res = Parser::with_flags(DEFAULT).parse(s).unwrap();
+        assert_eq!(res.fetch("lol").unwrap().get_type(), Type::Time);
+
+        let res = Parser::with_flags(NO_TIME).parse(s).unwrap();
+        assert_eq!(res.fetch("lol").unwrap().as_string(), Some("10min".to_string()));
+    }
+
+    #[test]
+    fn flag_no_implicit_arrays() {
+        let s = r#"lol = 1; lol = 2"#;
+
+        let res = Parser::with_flags(DEFAULT).parse(s).unwrap();
+        assert_eq!(res.fetch("lol").unwrap().get_type(), Type::Int);
+        assert!(res.fetch("lol").unwrap().is_implicit_array());
+
+        let res = Parser::with_flags(NO_IMPLICIT_ARRAYS).parse(s).unwrap();
+        assert_eq!(res.fetch("lol").unwrap().get_type(), Type::Array);
+        assert_eq!(res.fetch("lol").unwrap().iter().count(), 2);
+    }
+
+    #[test]
+    fn flag_save_comments() {
+        let s = "# comment\nlol = 10";
+        let res = Parser::with_flags(SAVE_COMMENTS).parse(s).unwrap();
+
+        assert_eq!(res.fetch("lol").unwrap().as_int(), Some(10));
+        assert_eq!(res.fetch("lol").unwrap().comments(), vec!["# comment".to_string()]);
+    }
+
+    #[test]
+    fn comments_not_saved() {
+        let res = Parser::new().parse("# hello\nlol = 10").unwrap();
+
+        assert!(res.fetch("lol").unwrap().comments().is_empty());
+    }
+
+    #[test]
+    fn comments_emitted() {
+        let res = Parser::with_flags(SAVE_COMMENTS).parse("# hello\nlol = 10").unwrap();
+        let out = Emitter::Config.emit(&res).unwrap();
+
+        assert!(out.contains("# hello"));
+        assert!(!Emitter::JSON.emit(&res).unwrap().contains("hello"));
+    }
+
+    #[test]
+    fn flag_disable_macro() {
+        let s = ".include \"/nonexistent/file.conf\"\nlol = 10";
+
+        assert!(Parser::with_flags(DEFAULT).parse(s).is_err());
+
+        let res = Parser::with_flags(DISABLE_MACRO).parse(s).unwrap();
+        assert_eq!(res.fetch("lol").unwrap().as_int(), Some(10));
+    }
+
+    #[test]
+    fn flag_no_filevars() {
+        let dir = TempDir::new("flag-no-filevars");
+        let path = dir.join("file.conf");
+        File::create(&path).unwrap().write_all(b"lol = $FILENAME").unwrap();
+
+        let res = Parser::with_flags(DEFAULT).parse_file(&path).unwrap();
+        assert!(res.fetch("lol").unwrap().as_string().unwrap().ends_with("file.conf"));
+
+        let res = Parser::with_flags(NO_FILEVARS).parse_file(&path).unwrap();
+        assert_eq!(res.fetch("lol").unwrap().as_string(), Some("$FILENAME".to_string()));
+    }
+
+    #[test]
+    fn untrusted_ignores_macros() {
+        let s = ".include \"/nonexistent/file.conf\"\n.priority 5\nlol = 10";
+        let res = Parser::untrusted().parse(s).unwrap();
+
+        assert_eq!(res.fetch("lol").unwrap().as_int(), Some(10));
+        assert_eq!(res.fetch("lol").unwrap().priority(), 0);
+    }
+
+    #[test]
+    fn limit_size() {
+        let mut p = Parser::new();
+        p.set_limits(Limits { size: 8, ..Default::default() });
+
+        match p.parse("lol = 1234567890") {
+            Err(error::Error::TooLarge(8)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn limit_size_included() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("big.conf"), b"lol = 1234567890".to_vec());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        p.set_limits(Limits { size: 20, ..Default::default() });
+
+        match p.parse(".include \"big.conf\"") {
+            Ok(_) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("big.conf"), b"lol = 12345678901234567890".to_vec());
+
+        let mut p = Parser::new();
+        p.set_limits(Limits { size: 20, ..Default::default() });
+        p.set_include_resolver(files);
+
+        match p.parse(".include \"big.conf\"") {
+            Err(error::Error::TooLarge(20)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn limit_depth() {
+        let s = "a { b { c = [1, [2]] } }";
+
+        let mut p = Parser::new();
+        p.set_limits(Limits { depth: 4, ..Default::default() });
+        assert!(p.parse(s).is_ok());
+
+        let mut p = Parser::new();
+        p.set_limits(Limits { depth: 3, ..Default::default() });
+        match p.parse(s) {
+            Err(error::Error::TooDeep(3)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn limit_objects() {
+        let s = "a = 1; a = 2; b = [1, 2, 3]";
+
+        let mut p = Parser::new();
+        p.set_limits(Limits { objects: 7, ..Default::default() });
+        assert!(p.parse(s).is_ok());
+
+        let mut p = Parser::new();
+        p.set_limits(Limits { objects: 6, ..Default::default() });
+        match p.parse(s) {
+            Err(error::Error::TooManyObjects(6)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn include_resolver() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("base.conf"), b"lol = 10".to_vec());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        let res = p.parse(".include \"base.conf\"\nfoo = bar").unwrap();
+
+        assert_eq!(res.fetch("lol").unwrap().as_int(), Some(10));
+        assert_eq!(res.fetch("foo").unwrap().as_string(), Some("bar".to_string()));
+    }
+
+    #[test]
+    fn include_search_paths() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("/first/base.conf"), b"lol = 1".to_vec());
+        files.insert(PathBuf::from("/second/base.conf"), b"lol = 2".to_vec());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        p.add_include_path("/second");
+        p.add_include_path("/first");
+        let res = p.parse(".include \"base.conf\"").unwrap();
+
+        assert_eq!(res.fetch("lol").unwrap().as_int(), Some(2));
+    }
+
+    #[test]
+    fn include_missing() {
+        let mut p = Parser::new();
+        p.set_include_resolver(HashMap::<PathBuf, Vec<u8>>::new());
+
+        match p.parse(".include \"missing.conf\"") {
+            Err(error::Error::Include(_)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+
+        let mut p = Parser::new();
+        p.set_include_resolver(HashMap::<PathBuf, Vec<u8>>::new());
+        assert!(p.parse(".try_include \"missing.conf\"\nlol = 1").is_ok());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(HashMap::<PathBuf, Vec<u8>>::new());
+        assert!(p.parse(".include(try=true) \"missing.conf\"\nlol = 1").is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "urls"))]
+    fn include_url_disabled() {
+        match Parser::new().parse(".include \"http://localhost/test.conf\"") {
+            Err(error::Error::UrlsDisabled(ref url)) => assert_eq!(url, "http://localhost/test.conf"),
+            res => panic!("unexpected result: {:?}", res)
+        }
+
+        match Parser::new().parse("section {\n  .include \"https://localhost/test.conf\"\n}") {
+            Err(error::Error::UrlsDisabled(ref url)) => assert_eq!(url, "https://localhost/test.conf"),
+            res => panic!("unexpected result: {:?}", res)
+        }
+
+        assert!(Parser::new().parse(".try_include \"http://localhost/test.conf\"\na = 1").is_ok());
+    }
+
+    #[test]
+    fn include_priority() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("base.conf"), b"lol = 10".to_vec());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        let res = p.parse(".include(priority=3) \"base.conf\"").unwrap();
+
+        assert_eq!(res.fetch("lol").unwrap().priority(), 3);
+    }
+
+    #[test]
+    fn include_invalid_priority() {
+        for prio in &["-1", "16"] {
+            let mut files = HashMap::new();
+            files.insert(PathBuf::from("base.conf"), b"lol = 10".to_vec());
+
+            let mut p = Parser::new();
+            p.set_include_resolver(files);
+            match p.parse(format!(".include(priority={}) \"base.conf\"", prio)) {
+                Err(error::Error::Include(_)) => (),
+                res => panic!("unexpected result: {:?}", res)
+            }
+        }
+    }
+
+    #[test]
+    fn include_cycle() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("a.conf"), b".include \"b.conf\"\na = 1".to_vec());
+        files.insert(PathBuf::from("b.conf"), b".try_include \"a.conf\"\nb = 1".to_vec());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        match p.parse(".include \"a.conf\"") {
+            Err(error::Error::Include(ref err)) if err.contains("cycle") => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn include_depth() {
+        let mut files = HashMap::new();
+        for n in 0..MAX_INCLUDE_DEPTH + 1 {
+            let doc = format!(".include \"{}.conf\"", n + 1);
+            files.insert(PathBuf::from(format!("{}.conf", n)), doc.into_bytes());
+        }
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        match p.parse(".include \"0.conf\"") {
+            Err(error::Error::Include(ref err)) if err.contains("nested") => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn locations() {
+        let mut p = Parser::new();
+        p.track_locations();
+        let res = p.parse("lol = 1;\n\nsection {\n  key = value\n}").unwrap();
+
+        assert_eq!(res.fetch("lol").unwrap().source_location().map(|loc| loc.line), Some(1));
+        assert_eq!(res.fetch_path("section.key").unwrap().source_location().map(|loc| loc.line), Some(4));
+
+        let res = Parser::new().parse("lol = 1").unwrap();
+        assert!(res.fetch("lol").unwrap().source_location().is_none());
+    }
+
+    #[test]
+    fn locations_included() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("/conf.d/net.conf"), b"# network\nport = 80;".to_vec());
+        files.insert(PathBuf::from("/conf.d/prio.conf"), b"port = 8080;".to_vec());
+
+        let mut p = Parser::new();
+        p.set_include_resolver(files);
+        p.track_locations();
+        let res = p.parse(".include \"/conf.d/net.conf\"\nhost = localhost\n.include(priority=1) \"/conf.d/prio.conf\"").unwrap();
+
+        let port = res.fetch("port").unwrap();
+        assert_eq!(port.as_int(), Some(8080));
+        assert_eq!(port.source_location().unwrap().to_string(), "/conf.d/prio.conf:1");
+
+        let host = res.fetch("host").unwrap();
+        assert_eq!(host.source_location().unwrap().to_string(), "<string>:2");
+    }
+
+    #[test]
+    fn locations_native_include() {
+        let mut p = Parser::new();
+        p.track_locations();
+        match p.parse(".try_include \"/nonexistent/ucl-rs.conf\"\na = 1;") {
+            Err(error::Error::Untracked(_)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn locations_replaced() {
+        let mut p = Parser::new();
+        p.track_locations();
+        let mut res = p.parse("a = 1;\nb = 2;").unwrap();
+        let old = res.fetch("a").unwrap();
+
+        res.pointer_mut("/a").unwrap().set(3).unwrap();
+        assert!(old.source_location().is_none());
+        assert!(res.fetch("a").unwrap().source_location().is_none());
+        assert_eq!(res.fetch("b").unwrap().source_location().map(|loc| loc.line), Some(2));
+    }
+
+    #[test]
+    fn locations_file() {
+        let dir = TempDir::new("locations-file");
+        let path = dir.join("file.conf");
+        File::create(&path).unwrap().write_all(b"a = 1;\nb = 2;").unwrap();
+
+        let mut p = Parser::new();
+        p.track_locations();
+        let res = p.parse_file(&path).unwrap();
+        let loc = res.fetch("b").unwrap().source_location().unwrap();
+
+        assert_eq!(loc.file, Some(path));
+        assert_eq!(loc.line, 2);
+    }
+
     #[test]
     fn variables() {
         let s = r#"lol = $LOL"#;
         let p = Parser::new();
-        p.register_var("LOL".to_string(), "test".to_string());
+        p.register_var("LOL".to_string(), "test".to_string()).unwrap();
         let res = p.parse(s).unwrap();
 
         assert_eq!(res.fetch("lol").unwrap().as_string(), Some("test".to_string()));
     }
+
+    fn conf_dir(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new(name);
+
+        for &(file, content) in files {
+            File::create(dir.join(file)).unwrap().write_all(content.as_bytes()).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn add_dir() {
+        let dir = conf_dir("add-dir", &[
+            ("b.conf", "a = 2; b = 2;"),
+            ("a.conf", "a = 1;"),
+            ("c.txt", "c = 3;")
+        ]);
+        fs::create_dir_all(dir.join("d.conf")).unwrap();
+
+        let mut p = Parser::new();
+        p.add_dir(&dir, "*.conf").unwrap();
+        let res = p.parse("e = 5").unwrap();
+
+        let a: Vec<_> = res.fetch("a").unwrap().values().filter_map(|v| v.as_int()).collect();
+        assert_eq!(a, vec![1, 2]);
+        assert_eq!(res.fetch("b").unwrap().as_int(), Some(2));
+        assert!(res.fetch("c").is_none());
+        assert_eq!(res.fetch("e").unwrap().as_int(), Some(5));
+    }
+
+    #[test]
+    fn add_dir_with_priorities() {
+        let dir = conf_dir("add-dir-with-priorities", &[
+            ("10-a.conf", "a = 1; b = 1;"),
+            ("20-b.conf", "a = 2;"),
+            ("30-c.conf", "c = 3;")
+        ]);
+
+        let mut p = Parser::new();
+        p.add_dir_with_priorities(&dir, "[0-9][0-9]-*.conf", 14).unwrap();
+        let res = p.finish().unwrap();
+
+        assert_eq!(res.fetch("a").unwrap().as_int(), Some(2));
+        assert_eq!(res.fetch("a").unwrap().priority(), 15);
+        assert_eq!(res.fetch("b").unwrap().priority(), 14);
+        assert_eq!(res.fetch("c").unwrap().priority(), 15);
+    }
+
+    #[test]
+    fn add_dir_errors() {
+        let dir = conf_dir("add-dir-errors", &[
+            ("a.conf", "a = 1;"),
+            ("b.conf", "b = ")
+        ]);
+
+        let mut p = Parser::new();
+        match p.add_dir(&dir, "*.conf") {
+            Err(error::Error::File(ref path, _)) if *path == dir.join("b.conf") => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+
+        let mut p = Parser::new();
+        match p.add_dir(dir.join("missing"), "*") {
+            Err(error::Error::Io(Some(ref path), Some(libc::ENOENT))) if *path == dir.join("missing") => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+
+        let mut p = Parser::new();
+        p.add_dir(&dir, "*.txt").unwrap();
+        assert_eq!(p.finish().unwrap().iter().count(), 0);
+    }
+
+    #[test]
+    fn add_dir_hidden_files() {
+        let dir = conf_dir("add-dir-hidden-files", &[
+            ("a.conf", "a = 1;"),
+            (".b.conf", "b = 1;")
+        ]);
+
+        let mut p = Parser::new();
+        p.add_dir(&dir, "*.conf").unwrap();
+        let res = p.finish().unwrap();
+        assert!(res.fetch("a").is_some());
+        assert!(res.fetch("b").is_none());
+
+        let mut p = Parser::new();
+        p.add_dir(&dir, ".*").unwrap();
+        let res = p.finish().unwrap();
+        assert!(res.fetch("a").is_none());
+        assert!(res.fetch("b").is_some());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn add_dir_non_utf8_name() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = conf_dir("add-dir-non-utf8-name", &[("a.conf", "a = 1;")]);
+
+        // some filesystems accept only valid UTF-8 names
+        if File::create(dir.join(OsStr::from_bytes(b"latin1-\xe9.conf"))).is_err() { return }
+
+        match Parser::new().add_dir(&dir, "*.conf") {
+            Err(error::Error::Conversion(_)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*.conf", "a.conf"));
+        assert!(glob_match("*.conf", ".conf"));
+        assert!(!glob_match("*.conf", "a.conf.bak"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(glob_match("[0-9]*", "10-a.conf"));
+        assert!(!glob_match("[!0-9]*", "10-a.conf"));
+        assert!(glob_match("[]x]", "]"));
+        assert!(glob_match("[", "["));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*a*b", "xaxxb"));
+        assert!(!glob_match("*a*b", "xaxxc"));
+
+        // backtracking over many stars must not take exponential time
+        let glob = "*a".repeat(30) + "b";
+        assert!(!glob_match(&glob, &"a".repeat(100)));
+    }
+
+    fn assert_io_error<P: AsRef<Path>>(res: Result<Object>, path: P, errno: i32) {
+        match res {
+            Err(error::Error::Io(Some(ref err_path), Some(err))) if err_path == path.as_ref() && err == errno => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn parse_file_missing() {
+        let dir = TempDir::new("parse-file-missing");
+        let path = dir.join("missing.conf");
+
+        assert_io_error(Parser::new().parse_file(&path), &path, libc::ENOENT);
+    }
+
+    #[test]
+    fn parse_file_directory() {
+        let dir = TempDir::new("parse-file-directory");
+
+        assert_io_error(Parser::new().parse_file(&dir), &dir, libc::EISDIR);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn parse_file_permission_denied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("parse-file-permission-denied");
+        let path = dir.join("file.conf");
+        File::create(&path).unwrap().write_all(b"a = 1").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // permissions are not enforced for root
+        if File::open(&path).is_err() {
+            assert_io_error(Parser::new().parse_file(&path), &path, libc::EACCES);
+        }
+    }
+
+    #[test]
+    fn parse_file_names() {
+        let dir = TempDir::new("parse-file-names");
+
+        for name in &["with space.conf", "zażółć.conf", "$HOME.conf", "quote\"d.conf", "-dash.conf"] {
+            let path = dir.join(name);
+            File::create(&path).unwrap().write_all(b"a = 1").unwrap();
+
+            let res = Parser::new().parse_file(&path).unwrap();
+            assert_eq!(res.fetch("a").unwrap().as_int(), Some(1), "{}", name);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn parse_file_non_utf8_name() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new("parse-file-non-utf8-name");
+        let path = dir.join(OsStr::from_bytes(b"latin1-\xe9.conf"));
+
+        // some filesystems accept only valid UTF-8 names
+        if File::create(&path).and_then(|mut file| file.write_all(b"a = 1")).is_err() { return }
+
+        let res = Parser::new().parse_file(&path).unwrap();
+        assert_eq!(res.fetch("a").unwrap().as_int(), Some(1));
+
+        fs::remove_file(&path).unwrap();
+        assert_io_error(Parser::new().parse_file(&path), &path, libc::ENOENT);
+    }
+
+    #[test]
+    fn filevars() {
+        let mut p = Parser::new();
+        p.set_filevars("/etc/app/app.conf", false).unwrap();
+        let res = p.parse("file = $FILENAME; dir = $CURDIR").unwrap();
+
+        assert_eq!(res.fetch("file").unwrap().as_string(), Some("/etc/app/app.conf".to_string()));
+        assert_eq!(res.fetch("dir").unwrap().as_string(), Some("/etc/app".to_string()));
+    }
+
+    #[test]
+    fn filevars_expand() {
+        let tmp = TempDir::new("filevars-expand");
+        File::create(tmp.join("base.conf")).unwrap().write_all(b"a = 1").unwrap();
+        let dir = fs::canonicalize(&tmp).unwrap();
+
+        let mut p = Parser::new();
+        p.set_filevars(dir.join(".").join("base.conf"), true).unwrap();
+        let res = p.parse(".include \"$CURDIR/base.conf\"\nfile = $FILENAME").unwrap();
+
+        assert_eq!(res.fetch("a").unwrap().as_int(), Some(1));
+        assert_eq!(res.fetch("file").unwrap().as_string(), dir.join("base.conf").to_str().map(String::from));
+
+        let mut p = Parser::new();
+        match p.set_filevars(dir.join("missing.conf"), true) {
+            Err(error::Error::Io(Some(_), Some(libc::ENOENT))) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+    }
+
+    #[test]
+    fn filevars_locations() {
+        let mut p = Parser::new();
+        p.track_locations();
+        p.set_filevars("/etc/app/app.conf", false).unwrap();
+        let res = p.parse("a = 1;\nb = 2;").unwrap();
+        let loc = res.fetch("b").unwrap().source_location().unwrap();
+
+        assert_eq!(loc.file, Some(PathBuf::from("/etc/app/app.conf")));
+        assert_eq!(loc.line, 2);
+    }
 }
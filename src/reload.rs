@@ -0,0 +1,407 @@
+//! Hot-reloadable configuration.
+//!
+//! `ConfigHandle` keeps the latest valid version of configuration file. When the file or any of
+//! documents it includes changes, it is parsed again with a fresh `Parser` created by the same
+//! function, validated, and swapped in only if validation passes. Readers take cheap `Frozen`
+//! snapshots, which stay unchanged by later reloads.
+//!
+//! Included documents are reported by libucl, so `.include` globs and parameters work as usual.
+//! If the parser enables custom `.include` resolution (see `include` module), documents loaded by
+//! `IncludeResolver` are tracked instead.
+//! Changes are detected by comparing modification time and size of files, either on demand with
+//! `ConfigHandle::reload_if_changed` or periodically by `ConfigHandle::watch`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::fs;
+//! use ucl::reload::ConfigHandle;
+//!
+//! let path = std::env::temp_dir().join(format!("ucl-rs-reload-doc-{}.conf", std::process::id()));
+//! fs::write(&path, "workers = 4").unwrap();
+//!
+//! let config = ConfigHandle::open(&path, ucl::Parser::new, |obj: &ucl::Object| {
+//!     match obj.fetch("workers").and_then(|w| w.as_int()) {
+//!         Some(n) if n > 0 => Ok(()),
+//!         _ => Err(ucl::Error::Validation("`workers` must be positive".to_string()))
+//!     }
+//! }).unwrap();
+//! assert_eq!(config.snapshot().fetch("workers").unwrap().as_int(), Some(4));
+//!
+//! // rejected by validation, previous version stays
+//! fs::write(&path, "workers = -1").unwrap();
+//! assert!(config.reload().is_err());
+//! assert_eq!(config.snapshot().fetch("workers").unwrap().as_int(), Some(4));
+//!
+//! fs::write(&path, "workers = 16").unwrap();
+//! assert!(config.reload().is_ok());
+//! assert_eq!(config.snapshot().fetch("workers").unwrap().as_int(), Some(16));
+//! # fs::remove_file(&path).unwrap();
+//! ```
+
+use libucl_sys::*;
+
+use error::Error;
+use object::{
+    Frozen,
+    Object
+};
+use parser::Parser;
+use Result;
+
+use std::ffi::CStr;
+use std::fs;
+use std::mem;
+use std::path::{
+    Path,
+    PathBuf
+};
+use std::sync::{
+    Arc,
+    Mutex,
+    MutexGuard,
+    RwLock
+};
+use std::sync::mpsc::{
+    self,
+    RecvTimeoutError
+};
+use std::thread;
+use std::time::{
+    Duration,
+    SystemTime
+};
+
+/// Check applied to every version of configuration before it is used.
+pub trait Validator {
+    /// Return `Err` if configuration should be rejected.
+    fn validate(&self, obj: &Object) -> Result<()>;
+}
+
+impl<F: Fn(&Object) -> Result<()>> Validator for F {
+    fn validate(&self, obj: &Object) -> Result<()> {
+        self(obj)
+    }
+}
+
+/// Validator checking configuration against UCL schema (JSON Schema draft v4).
+///
+/// Failures are reported as `Error::Validation`.
+///
+/// # Examples
+///
+/// ```rust
+/// use ucl::reload::{Schema, Validator};
+///
+/// let schema = ucl::Parser::new().parse(r#"
+///     type = object;
+///     properties { port { type = integer; } }
+///     required = [port];
+/// "#).unwrap();
+/// let schema = Schema::new(&schema).unwrap();
+///
+/// assert!(schema.validate(&ucl::Parser::new().parse("port = 80").unwrap()).is_ok());
+/// assert!(schema.validate(&ucl::Parser::new().parse("port = lol").unwrap()).is_err());
+/// ```
+pub struct Schema {
+    schema: Frozen
+}
+
+impl Schema {
+    /// Create validator from schema object.
+    pub fn new(schema: &Object) -> Result<Self> {
+        Ok(Schema { schema: schema.freeze()? })
+    }
+}
+
+impl Validator for Schema {
+    fn validate(&self, obj: &Object) -> Result<()> {
+        unsafe {
+            let mut err: ucl_schema_error = mem::zeroed();
+
+            if ucl_object_validate(self.schema.as_ptr(), obj.as_ptr(), &mut err) {
+                Ok(())
+            } else {
+                let msg = CStr::from_ptr(err.msg.as_ptr()).to_string_lossy().into_owned();
+
+                Err(Error::Validation(msg))
+            }
+        }
+    }
+}
+
+/// Modification time and size of file, `None` if it does not exist.
+type Stamp = Option<(Option<SystemTime>, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    fs::metadata(path).ok().map(|meta| (meta.modified().ok(), meta.len()))
+}
+
+struct Inner {
+    path: PathBuf,
+    parser: Box<dyn Fn() -> Parser + Send + Sync>,
+    validator: Box<dyn Validator + Send + Sync>,
+    current: RwLock<Frozen>,
+    // also serializes reloads
+    files: Mutex<Vec<(PathBuf, Stamp)>>
+}
+
+/// Handle to hot-reloadable configuration file.
+///
+/// Handles are cheap to clone and share the same configuration. See module documentation for
+/// details.
+#[derive(Clone)]
+pub struct ConfigHandle {
+    inner: Arc<Inner>
+}
+
+impl ConfigHandle {
+    /// Load configuration file.
+    ///
+    /// `parser` is called to create `Parser` for every reload, so it should apply all needed
+    /// settings (flags, variables, include paths). Fails if the first version cannot be loaded
+    /// or is rejected by `validator`.
+    pub fn open<P, F, V>(path: P, parser: F, validator: V) -> Result<Self>
+        where P: AsRef<Path>,
+              F: Fn() -> Parser + Send + Sync + 'static,
+              V: Validator + Send + Sync + 'static
+    {
+        let path = path.as_ref().to_path_buf();
+        let (config, files) = load(&path, &parser, &validator)?;
+
+        Ok(ConfigHandle {
+            inner: Arc::new(Inner {
+                path: path,
+                parser: Box::new(parser),
+                validator: Box::new(validator),
+                current: RwLock::new(config),
+                files: Mutex::new(files)
+            })
+        })
+    }
+
+    /// Return current version of configuration.
+    pub fn snapshot(&self) -> Frozen {
+        self.inner.current.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    /// Return paths of configuration file and all documents it included.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.stamps().iter().map(|&(ref path, _)| path.clone()).collect()
+    }
+
+    /// Load configuration again, even if no file has changed.
+    ///
+    /// On error current version is kept.
+    pub fn reload(&self) -> Result<()> {
+        let mut files = self.stamps();
+
+        self.swap(&mut files)
+    }
+
+    /// Load configuration again if any of its files has changed.
+    ///
+    /// Returns `true` if new version was swapped in. On error current version is kept, and
+    /// the same change is not reported again.
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let mut files = self.stamps();
+
+        if files.iter().all(|&(ref path, ref old)| stamp(path) == *old) {
+            return Ok(false)
+        }
+
+        // remember the change, so broken file is not parsed over and over again
+        for &mut (ref path, ref mut old) in files.iter_mut() {
+            *old = stamp(path);
+        }
+
+        self.swap(&mut files).map(|_| true)
+    }
+
+    /// Check files for changes every `interval` in background thread.
+    ///
+    /// `callback` is called with result of every reload attempt. Checking stops when returned
+    /// `Watcher` is dropped.
+    pub fn watch<F>(&self, interval: Duration, mut callback: F) -> Watcher
+        where F: FnMut(Result<()>) + Send + 'static
+    {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = self.clone();
+
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                match handle.reload_if_changed() {
+                    Ok(false) => (),
+                    Ok(true) => callback(Ok(())),
+                    Err(err) => callback(Err(err))
+                }
+            }
+        });
+
+        Watcher {
+            stop: Some(stop),
+            thread: Some(thread)
+        }
+    }
+
+    /// Lock list of files. Lock is never held while the list is inconsistent, so poisoning is
+    /// ignored.
+    fn stamps(&self) -> MutexGuard<'_, Vec<(PathBuf, Stamp)>> {
+        self.inner.files.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn swap(&self, files: &mut Vec<(PathBuf, Stamp)>) -> Result<()> {
+        let inner = &self.inner;
+        let (config, loaded) = load(&inner.path, &*inner.parser, &*inner.validator)?;
+
+        *inner.current.write().unwrap_or_else(|err| err.into_inner()) = config;
+        *files = loaded;
+
+        Ok(())
+    }
+}
+
+fn load<F, V>(path: &Path, parser: &F, validator: &V) -> Result<(Frozen, Vec<(PathBuf, Stamp)>)>
+    where F: Fn() -> Parser + ?Sized,
+          V: Validator + ?Sized
+{
+    // stamp files before parsing, so changes made while parsing are not missed
+    let main = (path.to_path_buf(), stamp(path));
+    let (obj, included) = parser().parse_file_with_includes(path)?;
+    validator.validate(&obj)?;
+
+    let files = Some(main).into_iter()
+        .chain(included.into_iter().map(|path| {
+            let stamp = stamp(&path);
+            (path, stamp)
+        }))
+        .collect();
+
+    // parsed tree is released when `obj` is dropped, frozen copy does not share its nodes
+    Ok((obj.freeze()?, files))
+}
+
+/// Background thread started by `ConfigHandle::watch`.
+///
+/// Dropping it stops the thread.
+pub struct Watcher {
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        // disconnecting the channel wakes the thread up
+        self.stop.take();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use utils::TempDir;
+
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn accept(_: &Object) -> Result<()> {
+        Ok(())
+    }
+
+    #[test]
+    fn reload_if_changed() {
+        let dir = TempDir::new("reload-if-changed");
+        fs::write(dir.join("main.conf"), ".include \"base.conf\"\na = 1").unwrap();
+        fs::write(dir.join("base.conf"), "b = 1").unwrap();
+
+        let parser_dir = dir.as_ref().to_path_buf();
+        let parser = move || {
+            let mut parser = Parser::new();
+            parser.add_include_path(&parser_dir);
+            parser
+        };
+        let config = ConfigHandle::open(dir.join("main.conf"), parser, accept).unwrap();
+        let old = config.snapshot();
+
+        assert_eq!(config.files(), vec![dir.join("main.conf"), dir.join("base.conf")]);
+        assert!(!config.reload_if_changed().unwrap());
+
+        fs::write(dir.join("base.conf"), "b = 20").unwrap();
+        assert!(config.reload_if_changed().unwrap());
+        assert_eq!(config.snapshot().fetch("b").unwrap().as_int(), Some(20));
+        assert_eq!(old.fetch("b").unwrap().as_int(), Some(1));
+        assert!(!config.reload_if_changed().unwrap());
+    }
+
+    #[test]
+    fn reload_glob_include() {
+        let dir = TempDir::new("reload-glob-include");
+        fs::create_dir_all(dir.join("conf.d")).unwrap();
+        fs::write(dir.join("main.conf"), ".include(priority=1) \"$CURDIR/conf.d/*.conf\"\na = 1").unwrap();
+        fs::write(dir.join("conf.d/base.conf"), "b = 1").unwrap();
+
+        let config = ConfigHandle::open(dir.join("main.conf"), Parser::new, accept).unwrap();
+        let base = fs::canonicalize(dir.join("conf.d/base.conf")).unwrap();
+
+        assert_eq!(config.files(), vec![dir.join("main.conf"), base]);
+        assert_eq!(config.snapshot().fetch("b").unwrap().priority(), 1);
+
+        fs::write(dir.join("conf.d/base.conf"), "b = 20").unwrap();
+        assert!(config.reload_if_changed().unwrap());
+        assert_eq!(config.snapshot().fetch("b").unwrap().as_int(), Some(20));
+    }
+
+    #[test]
+    fn reload_keeps_valid_version() {
+        let dir = TempDir::new("reload-keeps-valid-version");
+        let path = dir.join("main.conf");
+        fs::write(&path, "a = 1").unwrap();
+
+        let config = ConfigHandle::open(&path, Parser::new, accept).unwrap();
+
+        fs::write(&path, "a = ").unwrap();
+        assert!(config.reload_if_changed().is_err());
+        assert_eq!(config.snapshot().fetch("a").unwrap().as_int(), Some(1));
+        // broken version is not reported again
+        assert!(!config.reload_if_changed().unwrap());
+    }
+
+    #[test]
+    fn reload_schema() {
+        let dir = TempDir::new("reload-schema");
+        let path = dir.join("main.conf");
+        fs::write(&path, "port = 80").unwrap();
+
+        let schema = Parser::new().parse("properties { port { type = integer; } }").unwrap();
+        let config = ConfigHandle::open(&path, Parser::new, Schema::new(&schema).unwrap()).unwrap();
+
+        fs::write(&path, "port = eighty").unwrap();
+        match config.reload() {
+            Err(Error::Validation(_)) => (),
+            res => panic!("unexpected result: {:?}", res)
+        }
+        assert_eq!(config.snapshot().fetch("port").unwrap().as_int(), Some(80));
+    }
+
+    #[test]
+    fn watch() {
+        let dir = TempDir::new("reload-watch");
+        let path = dir.join("main.conf");
+        fs::write(&path, "a = 1").unwrap();
+
+        let config = ConfigHandle::open(&path, Parser::new, accept).unwrap();
+        let (tx, rx) = mpsc::channel();
+        let watcher = config.watch(Duration::from_millis(10), move |res| tx.send(res.is_ok()).unwrap());
+
+        fs::write(&path, "a = 100").unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(true));
+        assert_eq!(config.snapshot().fetch("a").unwrap().as_int(), Some(100));
+
+        drop(watcher);
+    }
+}
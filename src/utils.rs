@@ -1,16 +1,97 @@
 use libc::c_char;
 
+use error::Error;
+use Result;
+
 use std::str;
 use std::ffi::{
     CString,
     CStr
 };
+use std::path::{
+    Path,
+    PathBuf
+};
+
+/// Convert string to C string. Returned value must outlive pointers taken from it.
+pub fn to_c_str<T: AsRef<str>>(string: T) -> Result<CString> {
+    CString::new(string.as_ref())
+        .map_err(|_| Error::Conversion(format!("string contains NUL byte: {:?}", string.as_ref())))
+}
+
+/// Convert path to C string accepted by libucl.
+#[cfg(unix)]
+pub fn path_to_c_str(path: &Path) -> Result<CString> {
+    use std::os::unix::ffi::OsStrExt;
+
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| Error::Conversion(format!("path contains NUL byte: {}", path.display())))
+}
+
+/// Convert path to C string accepted by libucl.
+#[cfg(not(unix))]
+pub fn path_to_c_str(path: &Path) -> Result<CString> {
+    let path_str = path.to_str()
+        .ok_or_else(|| Error::Conversion(format!("path is not valid Unicode: {}", path.display())))?;
+
+    CString::new(path_str)
+        .map_err(|_| Error::Conversion(format!("path contains NUL byte: {}", path.display())))
+}
+
+/// Convert path reported by libucl to `PathBuf`.
+#[cfg(unix)]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
 
-pub fn to_c_str<T: AsRef<str>>(string: T) -> *const c_char {
-    CString::new(string.as_ref()).unwrap().as_ptr()
+/// Convert path reported by libucl to `PathBuf`.
+#[cfg(not(unix))]
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
 }
 
 pub fn to_str(cstring: *const c_char) -> Option<String> {
     if cstring.is_null() { return None }
-    Some(str::from_utf8(unsafe { CStr::from_ptr(cstring).to_bytes() }).unwrap().to_string())
+    str::from_utf8(unsafe { CStr::from_ptr(cstring).to_bytes() }).ok().map(String::from)
+}
+
+/// Temporary directory unique to a single test, removed with its content when dropped.
+#[cfg(test)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::{env, fs, process};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let path = env::temp_dir().join(format!("ucl-rs-{}-{}-{}", name, process::id(), n));
+        fs::create_dir_all(&path).unwrap();
+
+        TempDir(path)
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = ::std::fs::remove_dir_all(&self.0);
+    }
 }